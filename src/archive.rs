@@ -0,0 +1,665 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use flate2::read::GzDecoder;
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use tar::EntryType;
+use xz2::read::XzDecoder;
+
+// far more than any release unpacks to, an OTP source tree is under 1 GB
+const MAX_SIZE: u64 = 8 * 1024 * 1024 * 1024;
+const MAX_ENTRIES: u64 = 500_000;
+
+/// How much an archive may unpack to, so a broken or hostile archive can't
+/// fill the disk
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// total bytes of all the files
+    pub max_size: u64,
+    pub max_entries: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_size: MAX_SIZE,
+            max_entries: MAX_ENTRIES,
+        }
+    }
+}
+
+/// What a downloaded file is, told by its first bytes and not its name since
+/// mirrors and forks name and compress their artifacts differently
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    /// a Windows installer, which is run instead of unpacked
+    WindowsExecutable,
+    /// anything else, like the rebar3 escript, is used as it is
+    Other,
+}
+
+pub fn detect(file: &Path) -> Result<Format> {
+    let mut magic = [0; 262];
+    let mut reader = File::open(file).wrap_err_with(|| format!("Failed to open {file:?}"))?;
+    // a short read only means a file too small to be an archive
+    let n = reader.read(&mut magic)?;
+    let magic = &magic[..n];
+
+    let format = if magic.starts_with(&[0x1f, 0x8b]) {
+        Format::TarGz
+    } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Format::TarXz
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Format::TarZst
+    } else if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+        Format::Zip
+    } else if magic.starts_with(b"MZ") {
+        Format::WindowsExecutable
+    } else if magic.get(257..262) == Some(b"ustar") {
+        Format::Tar
+    } else {
+        Format::Other
+    };
+    debug!("{file:?} detected as {format:?}");
+
+    Ok(format)
+}
+
+/// Unpacks the archive into `dest` whatever its format, see `unpack_tar` for
+/// the entries that are refused
+pub fn unpack(file: &Path, dest: &Path) -> Result<()> {
+    let reader = File::open(file).wrap_err_with(|| format!("Failed to open {file:?}"))?;
+    match detect(file)? {
+        Format::Zip => extract_zip(reader, dest),
+        Format::Tar => unpack_tar(reader, dest),
+        Format::TarGz => unpack_tar(GzDecoder::new(reader), dest),
+        Format::TarXz => unpack_tar(XzDecoder::new(reader), dest),
+        Format::TarZst => unpack_tar(zstd::Decoder::new(reader)?, dest),
+        format @ (Format::WindowsExecutable | Format::Other) => {
+            Err(eyre!("{file:?} is not an archive but {format:?}"))
+        }
+    }
+}
+
+/// Unpacks the archive into `dest`, a directory in or the same as the release
+/// directory of an install, which is removed if unpacking fails so nothing is
+/// kept of an archive refused part way through
+pub fn unpack_release(file: &Path, dest: &Path, release_dir: &Path) -> Result<()> {
+    unpack(file, dest).inspect_err(|_| {
+        let _ = fs::remove_dir_all(release_dir);
+    })
+}
+
+/// Unpacks the tarball into `dest`, refusing the whole archive if an entry is
+/// an absolute path, has a `..`, is a link pointing outside of `dest` or isn't
+/// a file, directory or link, or if it is over the size limits
+pub fn unpack_tar<R: Read>(reader: R, dest: &Path) -> Result<()> {
+    unpack_tar_with_limits(reader, dest, &Limits::default())
+}
+
+pub fn unpack_tar_with_limits<R: Read>(reader: R, dest: &Path, limits: &Limits) -> Result<()> {
+    fs::create_dir_all(dest).wrap_err_with(|| format!("Failed to create {dest:?}"))?;
+    let dest = dest.canonicalize()?;
+
+    let mut archive = tar::Archive::new(reader);
+    let mut size = 0;
+    let mut count = 0;
+    // directories are unpacked last so their permissions can't prevent
+    // unpacking their contents, like `Archive::unpack` does
+    let mut directories = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // metadata for the entries around them, like the commit `git archive`
+        // puts first in every Github tarball, and never unpacked
+        if matches!(
+            entry.header().entry_type(),
+            EntryType::XGlobalHeader
+                | EntryType::XHeader
+                | EntryType::GNULongName
+                | EntryType::GNULongLink
+        ) {
+            continue;
+        }
+        let path = entry.path()?.to_path_buf();
+        debug!("checking archive entry {path:?}");
+
+        count += 1;
+        if count > limits.max_entries {
+            return Err(eyre!(
+                "Refusing to unpack archive with more than {} entries",
+                limits.max_entries
+            ));
+        }
+
+        check_path(&path).map_err(|reason| unsafe_entry(&path, &reason))?;
+        match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous => {
+                size += entry.size();
+                if size > limits.max_size {
+                    return Err(eyre!(
+                        "Refusing to unpack archive larger than {}",
+                        indicatif::HumanBytes(limits.max_size)
+                    ));
+                }
+            }
+            EntryType::Directory => (),
+            EntryType::Symlink => {
+                let target = link_name(&entry, &path)?;
+                check_symlink(&dest, &path, &target)
+                    .map_err(|reason| unsafe_entry(&path, &reason))?;
+            }
+            // hard links name another entry of the archive
+            EntryType::Link => {
+                let target = link_name(&entry, &path)?;
+                check_path(&target).map_err(|reason| unsafe_entry(&path, &reason))?;
+            }
+            entry_type => {
+                return Err(unsafe_entry(
+                    &path,
+                    &format!("it is an unsupported {entry_type:?} entry"),
+                ))
+            }
+        }
+
+        if entry.header().entry_type() == EntryType::Directory {
+            directories.push(entry);
+        } else {
+            entry
+                .unpack_in(&dest)
+                .wrap_err_with(|| format!("Failed to unpack {path:?}"))?;
+        }
+    }
+
+    for mut directory in directories {
+        directory.unpack_in(&dest)?;
+    }
+
+    Ok(())
+}
+
+/// Extracts the zip archive into `dest`, with the same checks as `unpack_tar`
+pub fn extract_zip<R: Read + Seek>(reader: R, dest: &Path) -> Result<()> {
+    extract_zip_with_limits(reader, dest, &Limits::default())
+}
+
+pub fn extract_zip_with_limits<R: Read + Seek>(
+    reader: R,
+    dest: &Path,
+    limits: &Limits,
+) -> Result<()> {
+    fs::create_dir_all(dest).wrap_err_with(|| format!("Failed to create {dest:?}"))?;
+    let dest = dest.canonicalize()?;
+
+    let mut archive = zip::ZipArchive::new(reader)?;
+    if archive.len() as u64 > limits.max_entries {
+        return Err(eyre!(
+            "Refusing to unpack archive with more than {} entries",
+            limits.max_entries
+        ));
+    }
+
+    let mut size: u64 = 0;
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        let path = PathBuf::from(file.name());
+        debug!("checking archive entry {path:?}");
+
+        check_path(&path).map_err(|reason| unsafe_entry(&path, &reason))?;
+        if file.enclosed_name().is_none() {
+            return Err(unsafe_entry(&path, "it isn't a relative path"));
+        }
+
+        size = size.saturating_add(file.size());
+        if size > limits.max_size {
+            return Err(eyre!(
+                "Refusing to unpack archive larger than {}",
+                indicatif::HumanBytes(limits.max_size)
+            ));
+        }
+
+        let out_path = dest.join(&path);
+        if file.is_dir() {
+            fs::create_dir_all(&out_path)
+                .wrap_err_with(|| format!("Failed to create {out_path:?}"))?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).wrap_err_with(|| format!("Failed to create {parent:?}"))?;
+        }
+
+        if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            let target = PathBuf::from(target);
+            check_symlink(&dest, &path, &target).map_err(|reason| unsafe_entry(&path, &reason))?;
+            symlink(&target, &out_path).wrap_err_with(|| format!("Failed to unpack {path:?}"))?;
+        } else {
+            let mut out_file = File::create(&out_path)
+                .wrap_err_with(|| format!("Failed to create {out_path:?}"))?;
+            std::io::copy(&mut file, &mut out_file)
+                .wrap_err_with(|| format!("Failed to unpack {path:?}"))?;
+            set_mode(&out_path, file.unix_mode())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, path: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, path: &Path) -> std::io::Result<()> {
+    match path.parent().map(|parent| parent.join(target)) {
+        Some(target_path) if target_path.is_dir() => {
+            std::os::windows::fs::symlink_dir(target, path)
+        }
+        _ => std::os::windows::fs::symlink_file(target, path),
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> Result<()> {
+    Ok(())
+}
+
+fn unsafe_entry(path: &Path, reason: &str) -> color_eyre::Report {
+    eyre!("Refusing to unpack archive entry {path:?}: {reason}")
+}
+
+fn link_name<R: Read>(entry: &tar::Entry<R>, path: &Path) -> Result<PathBuf> {
+    match entry.link_name()? {
+        Some(target) => Ok(target.to_path_buf()),
+        None => Err(unsafe_entry(path, "it is a link without a target")),
+    }
+}
+
+fn check_path(path: &Path) -> Result<(), String> {
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                return Err("it is an absolute path".to_string())
+            }
+            Component::ParentDir => return Err("it has a `..` in its path".to_string()),
+            Component::CurDir | Component::Normal(_) => (),
+        }
+    }
+
+    Ok(())
+}
+
+// a symlink's target is relative to the directory it is in, which is resolved
+// as it is on disk so links through links already unpacked are followed too
+fn check_symlink(dest: &Path, path: &Path, target: &Path) -> Result<(), String> {
+    if target.has_root() || matches!(target.components().next(), Some(Component::Prefix(_))) {
+        return Err(format!("it links to the absolute path {target:?}"));
+    }
+
+    let parent = dest.join(path.parent().unwrap_or(Path::new("")));
+    let resolved = normalize(&real_path(&parent).join(target));
+    if resolved.starts_with(dest) {
+        Ok(())
+    } else {
+        Err(format!("it links to {target:?} outside of the archive"))
+    }
+}
+
+// the canonical path of the deepest ancestor that exists with the rest of the
+// path appended, since the rest is still to be unpacked
+fn real_path(path: &Path) -> PathBuf {
+    let mut existing = path.to_path_buf();
+    let mut rest = vec![];
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return rest.into_iter().rev().fold(canonical, |p, c| p.join(c));
+        }
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name.to_os_string());
+                existing = parent.to_path_buf();
+            }
+            _ => return normalize(path),
+        }
+    }
+}
+
+// removes `.` and `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use tempdir::TempDir;
+
+    // entries are written with raw headers since `tar::Builder` itself
+    // refuses to write most of the hostile paths
+    fn header(path: &str, entry_type: EntryType, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_ustar();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(0o755);
+        header
+    }
+
+    fn tarball(entries: &[(&str, EntryType, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, entry_type, contents) in entries {
+            let mut header = match entry_type {
+                EntryType::Symlink | EntryType::Link => {
+                    let mut header = header(path, *entry_type, 0);
+                    header.as_old_mut().linkname[..contents.len()]
+                        .copy_from_slice(contents.as_bytes());
+                    header
+                }
+                _ => header(path, *entry_type, contents.len() as u64),
+            };
+            header.set_cksum();
+            let data: &[u8] = match entry_type {
+                EntryType::Regular | EntryType::XGlobalHeader | EntryType::XHeader => {
+                    contents.as_bytes()
+                }
+                _ => &[],
+            };
+            builder.append(&header, data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn unpack_entries(entries: &[(&str, EntryType, &str)]) -> (TempDir, Result<()>) {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let dest = dir.path().join("dest");
+        let result = unpack_tar(Cursor::new(tarball(entries)), &dest);
+        (dir, result)
+    }
+
+    fn assert_refused(result: Result<()>, reason: &str) {
+        let err = result.expect_err("archive should be refused").to_string();
+        assert!(err.contains(reason), "unexpected error: {err}");
+    }
+
+    #[test]
+    fn unpacks_files_dirs_and_links() {
+        let (dir, result) = unpack_entries(&[
+            ("otp/", EntryType::Directory, ""),
+            ("otp/bin/erl", EntryType::Regular, "#!/bin/sh"),
+            ("otp/erl", EntryType::Symlink, "bin/erl"),
+            ("otp/bin/erlc", EntryType::Symlink, "../bin/erl"),
+            ("otp/erl2", EntryType::Link, "otp/bin/erl"),
+        ]);
+        result.unwrap();
+        let dest = dir.path().join("dest");
+        assert_eq!(
+            fs::read_to_string(dest.join("otp/erl")).unwrap(),
+            "#!/bin/sh"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("otp/bin/erlc")).unwrap(),
+            "#!/bin/sh"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("otp/erl2")).unwrap(),
+            "#!/bin/sh"
+        );
+    }
+
+    #[test]
+    fn unpacks_git_archive_tarballs() {
+        // `git archive` starts with a pax global header holding the commit and
+        // uses pax headers for paths too long for the tar header
+        let long_path = format!("gleam-1.0.0/{}/lib.rs", "compiler".repeat(12));
+        let pax_path = format!("path={long_path}\n");
+        let pax_path = format!("{} {pax_path}", pax_path.len() + 4);
+        let (dir, result) = unpack_entries(&[
+            (
+                "pax_global_header",
+                EntryType::XGlobalHeader,
+                "52 comment=0123456789abcdef0123456789abcdef01234567\n",
+            ),
+            ("gleam-1.0.0/", EntryType::Directory, ""),
+            ("gleam-1.0.0/README.md", EntryType::Regular, "Gleam"),
+            ("PaxHeaders/lib.rs", EntryType::XHeader, &pax_path),
+            ("gleam-1.0.0/lib.rs", EntryType::Regular, "fn main() {}"),
+        ]);
+        result.unwrap();
+        let dest = dir.path().join("dest");
+        assert_eq!(
+            fs::read_to_string(dest.join("gleam-1.0.0/README.md")).unwrap(),
+            "Gleam"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join(&long_path)).unwrap(),
+            "fn main() {}"
+        );
+        assert!(!dest.join("pax_global_header").exists());
+    }
+
+    #[test]
+    fn refuses_absolute_paths() {
+        let (dir, result) = unpack_entries(&[("/tmp/beamup-evil", EntryType::Regular, "evil")]);
+        assert_refused(result, "absolute path");
+        assert!(!dir.path().join("dest/tmp").exists());
+    }
+
+    #[test]
+    fn refuses_parent_dirs() {
+        let (dir, result) = unpack_entries(&[
+            ("otp/ok", EntryType::Regular, "ok"),
+            ("otp/../../evil", EntryType::Regular, "evil"),
+        ]);
+        assert_refused(result, "`..`");
+        assert!(!dir.path().join("evil").exists());
+    }
+
+    #[test]
+    fn refuses_symlinks_outside() {
+        let (_dir, result) = unpack_entries(&[("otp/etc", EntryType::Symlink, "/etc")]);
+        assert_refused(result, "absolute path");
+
+        let (_dir, result) = unpack_entries(&[("otp/bin/up", EntryType::Symlink, "../../..")]);
+        assert_refused(result, "outside of the archive");
+    }
+
+    #[test]
+    fn refuses_symlinks_escaping_through_symlinks() {
+        // `otp/here` is the root of the archive, so `otp/here/up` going up one
+        // dir would be outside of it even though `otp/up` wouldn't be
+        let (dir, result) = unpack_entries(&[
+            ("otp/here", EntryType::Symlink, ".."),
+            ("otp/here/up", EntryType::Symlink, ".."),
+        ]);
+        assert_refused(result, "outside of the archive");
+        assert!(fs::symlink_metadata(dir.path().join("dest/up")).is_err());
+    }
+
+    #[test]
+    fn refuses_hard_links_outside() {
+        let (_dir, result) = unpack_entries(&[("otp/passwd", EntryType::Link, "/etc/passwd")]);
+        assert_refused(result, "absolute path");
+
+        let (_dir, result) = unpack_entries(&[("otp/passwd", EntryType::Link, "../../etc/passwd")]);
+        assert_refused(result, "`..`");
+    }
+
+    #[test]
+    fn refuses_devices() {
+        let (_dir, result) = unpack_entries(&[("otp/null", EntryType::Char, "")]);
+        assert_refused(result, "unsupported");
+    }
+
+    #[test]
+    fn refuses_oversized_archives() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let tarball = tarball(&[
+            ("a", EntryType::Regular, "0123456789"),
+            ("b", EntryType::Regular, "0123456789"),
+        ]);
+        let limits = Limits {
+            max_size: 15,
+            max_entries: 10,
+        };
+        let result = unpack_tar_with_limits(Cursor::new(&tarball), dir.path(), &limits);
+        assert_refused(result, "larger than");
+
+        let limits = Limits {
+            max_size: 100,
+            max_entries: 1,
+        };
+        let result = unpack_tar_with_limits(Cursor::new(&tarball), dir.path(), &limits);
+        assert_refused(result, "more than 1 entries");
+    }
+
+    fn zip(entries: &[(&str, Option<&str>, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+        for (path, symlink, contents) in entries {
+            match symlink {
+                Some(target) => writer.add_symlink(*path, *target, options).unwrap(),
+                None => {
+                    writer.start_file(*path, options).unwrap();
+                    writer.write_all(contents.as_bytes()).unwrap();
+                }
+            }
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn detects_and_unpacks_formats_by_content() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let tar = tarball(&[("bin/gleam", EntryType::Regular, "gleam")]);
+
+        let mut gz = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        gz.write_all(&tar).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(vec![], 6);
+        xz.write_all(&tar).unwrap();
+        let archives = [
+            ("a.tar", tar.clone(), Format::Tar),
+            // names don't matter, only the contents
+            ("b.zip", gz.finish().unwrap(), Format::TarGz),
+            ("c", xz.finish().unwrap(), Format::TarXz),
+            (
+                "d.tar.gz",
+                zstd::encode_all(&tar[..], 0).unwrap(),
+                Format::TarZst,
+            ),
+            (
+                "e.tar.gz",
+                zip(&[("bin/gleam", None, "gleam")]),
+                Format::Zip,
+            ),
+        ];
+        for (name, contents, format) in archives {
+            let file = dir.path().join(name);
+            fs::write(&file, contents).unwrap();
+            assert_eq!(detect(&file).unwrap(), format, "{name}");
+
+            let dest = dir.path().join(format!("{name}-dest"));
+            unpack(&file, &dest).unwrap();
+            assert_eq!(fs::read_to_string(dest.join("bin/gleam")).unwrap(), "gleam");
+        }
+
+        let escript = dir.path().join("rebar3");
+        fs::write(&escript, "#!/usr/bin/env escript\n").unwrap();
+        assert_eq!(detect(&escript).unwrap(), Format::Other);
+        assert!(unpack(&escript, &dir.path().join("rebar3-dest")).is_err());
+    }
+
+    #[test]
+    fn extracts_zips() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let zip = zip(&[("bin/elp", None, "elp")]);
+        extract_zip(Cursor::new(zip), dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("bin/elp")).unwrap(),
+            "elp"
+        );
+    }
+
+    #[test]
+    fn refuses_hostile_zips() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let dest = dir.path().join("dest");
+
+        let zip_file = zip(&[("../evil", None, "evil")]);
+        assert_refused(extract_zip(Cursor::new(zip_file), &dest), "`..`");
+
+        let zip_file = zip(&[("/tmp/beamup-evil", None, "evil")]);
+        assert_refused(extract_zip(Cursor::new(zip_file), &dest), "absolute path");
+
+        let zip_file = zip(&[("bin/up", Some("../../.."), "")]);
+        assert_refused(
+            extract_zip(Cursor::new(zip_file), &dest),
+            "outside of the archive",
+        );
+
+        let zip_file = zip(&[("a", None, "0123456789"), ("b", None, "0123456789")]);
+        let limits = Limits {
+            max_size: 15,
+            max_entries: 10,
+        };
+        assert_refused(
+            extract_zip_with_limits(Cursor::new(zip_file), &dest, &limits),
+            "larger than",
+        );
+        assert!(!dir.path().join("evil").exists());
+    }
+
+    #[test]
+    fn refuses_zip_symlinks_escaping_through_symlinks() {
+        // each link is checked against the ones extracted before it, so
+        // `a/b` is resolved through `a`, the root of the archive
+        let dir = TempDir::new("beamup-test").unwrap();
+        let dest = dir.path().join("dest");
+        let zip_file = zip(&[("a", Some("."), ""), ("a/b", Some(".."), "")]);
+        assert_refused(
+            extract_zip(Cursor::new(zip_file), &dest),
+            "outside of the archive",
+        );
+        assert!(fs::symlink_metadata(dest.join("b")).is_err());
+
+        let dest = dir.path().join("dest2");
+        let zip_file = zip(&[
+            ("otp/bin/erl", None, "#!/bin/sh"),
+            ("otp/erl", Some("bin/erl"), ""),
+            ("otp/bin/erlc", Some("../bin/erl"), ""),
+        ]);
+        extract_zip(Cursor::new(zip_file), &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("otp/bin/erlc")).unwrap(),
+            "#!/bin/sh"
+        );
+    }
+}
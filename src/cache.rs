@@ -0,0 +1,356 @@
+use crate::config;
+use color_eyre::eyre::{Result, WrapErr};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static INDEX_FILE: &str = "index.toml";
+
+// downloads are stored by the sha256 of their content in `blobs` and the index
+// maps the URL they were downloaded from to the sha256, so a URL is only ever
+// downloaded once and the same content is only stored once
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct Index {
+    #[serde(default)]
+    downloads: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+struct Entry {
+    sha256: String,
+    size: u64,
+    // seconds since the epoch, the least recently used are evicted first
+    last_used: u64,
+}
+
+pub fn cache_dir() -> Result<PathBuf> {
+    Ok(config::data_dir()?.join("beamup").join("cache"))
+}
+
+/// The cached download of the URL, if there is one
+pub fn lookup(url: &str) -> Option<PathBuf> {
+    let cache_dir = cache_dir().ok()?;
+    let mut index = read_index(&cache_dir);
+    let entry = index.downloads.get_mut(url)?;
+
+    let blob = cache_dir.join("blobs").join(&entry.sha256);
+    if !blob.is_file() {
+        debug!("cached download of {url} is missing {blob:?}");
+        return None;
+    }
+
+    entry.last_used = now();
+    let _ = write_index(&cache_dir, &index);
+
+    Some(blob)
+}
+
+/// Adds the file downloaded from the URL, with the given sha256, to the cache,
+/// evicting the least recently used downloads if the cache grows over its size
+/// limit
+pub fn insert(url: &str, file: &Path, sha256: &str, config: &config::Config) -> Result<()> {
+    insert_into(&cache_dir()?, url, file, sha256, config)
+}
+
+fn insert_into(
+    cache_dir: &Path,
+    url: &str,
+    file: &Path,
+    sha256: &str,
+    config: &config::Config,
+) -> Result<()> {
+    let blobs_dir = cache_dir.join("blobs");
+    fs::create_dir_all(&blobs_dir)
+        .wrap_err_with(|| format!("Failed to create cache directory {blobs_dir:?}"))?;
+
+    let blob = blobs_dir.join(sha256);
+    if !blob.is_file() {
+        // copy then rename so an interrupted copy is never taken for the blob
+        let tmp = blobs_dir.join(format!("{sha256}.tmp"));
+        fs::copy(file, &tmp)
+            .wrap_err_with(|| format!("Failed to copy {file:?} into cache {tmp:?}"))?;
+        fs::rename(&tmp, &blob)?;
+    }
+
+    let mut index = read_index(cache_dir);
+    index.downloads.insert(
+        url.to_string(),
+        Entry {
+            sha256: sha256.to_string(),
+            size: fs::metadata(&blob)?.len(),
+            last_used: now(),
+        },
+    );
+    evict(
+        cache_dir,
+        &mut index,
+        config::lookup_cache_size_limit(config),
+        sha256,
+    );
+    write_index(cache_dir, &index)
+}
+
+/// Forgets the cached download of the URL, removing its blob if no other URL
+/// has the same content
+pub fn remove(url: &str) {
+    if let Ok(cache_dir) = cache_dir() {
+        remove_from(&cache_dir, url);
+    }
+}
+
+fn remove_from(cache_dir: &Path, url: &str) {
+    let mut index = read_index(cache_dir);
+    if let Some(entry) = index.downloads.remove(url) {
+        if !index.downloads.values().any(|e| e.sha256 == entry.sha256) {
+            let _ = fs::remove_file(cache_dir.join("blobs").join(&entry.sha256));
+        }
+        let _ = write_index(cache_dir, &index);
+    }
+}
+
+pub fn print_downloads(config: &config::Config) -> Result<()> {
+    let cache_dir = cache_dir()?;
+    let index = read_index(&cache_dir);
+
+    let mut total = 0;
+    for (url, entry) in index.downloads.iter() {
+        println!(
+            "{}  {:>10}  {}",
+            &entry.sha256[..12],
+            indicatif::HumanBytes(entry.size).to_string(),
+            url
+        );
+        total += entry.size;
+    }
+    println!();
+    println!(
+        "{} of {} used in {}",
+        indicatif::HumanBytes(total),
+        indicatif::HumanBytes(config::lookup_cache_size_limit(config)),
+        cache_dir.display()
+    );
+
+    Ok(())
+}
+
+pub fn clean() -> Result<()> {
+    let cache_dir = cache_dir()?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)
+            .wrap_err_with(|| format!("Failed to remove cache directory {cache_dir:?}"))?;
+    }
+
+    Ok(())
+}
+
+pub fn sha256_file(file: &Path) -> Result<String> {
+    let mut reader =
+        fs::File::open(file).wrap_err_with(|| format!("Failed to open {file:?} to hash"))?;
+    let mut context = digest::Context::new(&digest::SHA256);
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        context.update(&buffer[..n]);
+    }
+
+    Ok(context
+        .finish()
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+// removes the least recently used downloads until the cache fits in the limit,
+// never removing the blob just added
+fn evict(cache_dir: &Path, index: &mut Index, limit: u64, keep: &str) {
+    let mut blobs: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for entry in index.downloads.values() {
+        let (_, last_used) = blobs.entry(entry.sha256.clone()).or_insert((entry.size, 0));
+        *last_used = (*last_used).max(entry.last_used);
+    }
+
+    let mut total: u64 = blobs.values().map(|(size, _)| size).sum();
+    let mut by_age: Vec<(String, u64, u64)> = blobs
+        .into_iter()
+        .map(|(sha256, (size, last_used))| (sha256, size, last_used))
+        .collect();
+    by_age.sort_by_key(|(_, _, last_used)| *last_used);
+
+    for (sha256, size, _) in by_age {
+        if total <= limit {
+            break;
+        }
+        if sha256 == keep {
+            continue;
+        }
+        debug!("evicting {sha256} from the download cache");
+        let _ = fs::remove_file(cache_dir.join("blobs").join(&sha256));
+        index.downloads.retain(|_, entry| entry.sha256 != sha256);
+        total -= size;
+    }
+}
+
+fn read_index(cache_dir: &Path) -> Index {
+    fs::read_to_string(cache_dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|index| toml::from_str(&index).ok())
+        .unwrap_or_default()
+}
+
+fn write_index(cache_dir: &Path, index: &Index) -> Result<()> {
+    let index_file = cache_dir.join(INDEX_FILE);
+    let toml_string = toml::to_string(index)?;
+    fs::write(&index_file, toml_string)
+        .wrap_err_with(|| format!("Failed to write cache index {index_file:?}"))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    fn entry(sha256: &str, size: u64, last_used: u64) -> Entry {
+        Entry {
+            sha256: sha256.to_string(),
+            size,
+            last_used,
+        }
+    }
+
+    // a cache with a blob of the given size for each entry
+    fn cache(entries: &[(&str, Entry)]) -> (TempDir, Index) {
+        let dir = TempDir::new("beamup-test").unwrap();
+        fs::create_dir_all(dir.path().join("blobs")).unwrap();
+        let mut index = Index::default();
+        for (url, entry) in entries {
+            let blob = vec![0; entry.size as usize];
+            fs::write(dir.path().join("blobs").join(&entry.sha256), blob).unwrap();
+            index.downloads.insert(url.to_string(), entry.clone());
+        }
+        (dir, index)
+    }
+
+    fn config(extra: &str) -> config::Config {
+        toml::from_str(&format!("install_dir = \"\"\n{extra}")).unwrap()
+    }
+
+    fn has_blob(dir: &TempDir, sha256: &str) -> bool {
+        dir.path().join("blobs").join(sha256).is_file()
+    }
+
+    #[test]
+    fn evicts_least_recently_used_over_the_limit() {
+        let (dir, mut index) = cache(&[
+            ("https://a", entry("a", 10, 1)),
+            ("https://b", entry("b", 10, 2)),
+            ("https://c", entry("c", 10, 3)),
+        ]);
+
+        evict(dir.path(), &mut index, 30, "c");
+        assert_eq!(index.downloads.len(), 3);
+
+        evict(dir.path(), &mut index, 25, "c");
+        assert!(!index.downloads.contains_key("https://a"));
+        assert!(!has_blob(&dir, "a"));
+        assert!(has_blob(&dir, "b") && has_blob(&dir, "c"));
+
+        evict(dir.path(), &mut index, 0, "c");
+        assert_eq!(index.downloads.keys().collect::<Vec<_>>(), ["https://c"],);
+        assert!(!has_blob(&dir, "b"));
+    }
+
+    #[test]
+    fn keeps_the_blob_just_inserted() {
+        // the blob just inserted is kept even when it is the oldest and alone
+        // over the limit
+        let (dir, mut index) = cache(&[
+            ("https://old", entry("old", 100, 1)),
+            ("https://new", entry("new", 10, 2)),
+        ]);
+
+        evict(dir.path(), &mut index, 50, "old");
+        assert!(index.downloads.contains_key("https://old"));
+        assert!(has_blob(&dir, "old"));
+        assert!(!index.downloads.contains_key("https://new"));
+        assert!(!has_blob(&dir, "new"));
+    }
+
+    #[test]
+    fn evicts_shared_blobs_by_their_latest_use() {
+        // a blob shared by URLs counts once towards the size and is as
+        // recently used as the most recent of them
+        let (dir, mut index) = cache(&[
+            ("https://github.com/x", entry("shared", 10, 1)),
+            ("https://mirror/x", entry("shared", 10, 5)),
+            ("https://y", entry("y", 10, 3)),
+            ("https://z", entry("z", 10, 4)),
+        ]);
+
+        evict(dir.path(), &mut index, 20, "z");
+        assert!(!index.downloads.contains_key("https://y"));
+        assert!(index.downloads.contains_key("https://github.com/x"));
+        assert!(index.downloads.contains_key("https://mirror/x"));
+        assert!(has_blob(&dir, "shared"));
+
+        // evicting a shared blob drops every URL pointing to it
+        evict(dir.path(), &mut index, 10, "z");
+        assert_eq!(index.downloads.keys().collect::<Vec<_>>(), ["https://z"]);
+        assert!(!has_blob(&dir, "shared"));
+    }
+
+    #[test]
+    fn stores_the_same_content_once() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let file = dir.path().join("erlang.tar.gz");
+        fs::write(&file, "erlang").unwrap();
+        let sha256 = sha256_file(&file).unwrap();
+
+        let config = config("");
+        insert_into(dir.path(), "https://github.com/x", &file, &sha256, &config).unwrap();
+        insert_into(dir.path(), "https://mirror/x", &file, &sha256, &config).unwrap();
+        assert_eq!(fs::read_dir(dir.path().join("blobs")).unwrap().count(), 1);
+        assert_eq!(read_index(dir.path()).downloads.len(), 2);
+
+        // the blob stays until no URL has it
+        remove_from(dir.path(), "https://github.com/x");
+        assert!(has_blob(&dir, &sha256));
+        remove_from(dir.path(), "https://mirror/x");
+        assert!(!has_blob(&dir, &sha256));
+        assert!(read_index(dir.path()).downloads.is_empty());
+    }
+
+    #[test]
+    fn evicts_over_the_limit_of_the_config_given() {
+        // a config passed with `-c`, not the one in the home dir
+        let config = config("cache_size_limit = 1");
+        let dir = TempDir::new("beamup-test").unwrap();
+        let mut blobs = vec![];
+        for name in ["a", "b"] {
+            let file = dir.path().join(name);
+            fs::write(&file, vec![name.as_bytes()[0]; 600 * 1024]).unwrap();
+            let sha256 = sha256_file(&file).unwrap();
+            insert_into(dir.path(), name, &file, &sha256, &config).unwrap();
+            blobs.push(sha256);
+        }
+
+        let index = read_index(dir.path());
+        assert_eq!(index.downloads.keys().collect::<Vec<_>>(), ["b"]);
+        assert!(!has_blob(&dir, &blobs[0]));
+        assert!(has_blob(&dir, &blobs[1]));
+    }
+}
@@ -1,8 +1,22 @@
+pub mod branches;
 pub mod build;
+pub mod build_log;
+pub mod bundle;
+pub mod cache;
+pub mod component_default;
+pub mod component_delete;
 pub mod component_install;
+pub mod component_list;
+pub mod component_switch;
 pub mod default;
+pub mod delete;
+pub mod fetch;
+pub mod info;
 pub mod install;
+pub mod link;
 pub mod list;
 pub mod releases;
+pub mod repo;
 pub mod switch;
+pub mod tags;
 pub mod update_links;
@@ -1,18 +1,28 @@
+use crate::archive;
 use crate::config;
+use crate::dependencies::{Dependency, Distro};
+use crate::git;
 use crate::git::GitRef;
-use crate::github::download_release_tarball;
+use crate::github;
+use crate::github::{download_ref_tarball, download_release_tarball, GithubRepo};
+use crate::interrupt;
 use crate::languages;
-use color_eyre::{eyre::Result, eyre::WrapErr};
+use crate::languages::erlang::{Docs, EmuType};
+use crate::mirror::Mirror;
+use crate::utils;
+use color_eyre::{eyre::eyre, eyre::Result, eyre::WrapErr};
 use console::Emoji;
-use flate2::read::GzDecoder;
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
-use std::path::Path;
-use std::process::Command;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
 use std::time::Duration;
 use std::time::Instant;
-use tar::Archive;
 use tempdir::TempDir;
 
 // http://unicode.org/emoji/charts/full-emoji-list.html
@@ -21,79 +31,438 @@ static FAIL: Emoji = Emoji("❌", "❌ ");
 static WARNING: Emoji = Emoji("🚫", "🚫");
 
 #[derive(Copy, Clone)]
-enum BuildResult {
+pub enum BuildResult {
     Success,
     Fail,
 }
 
-struct CheckContext<'a> {
-    src_dir: &'a Path,
-    install_dir: &'a Path,
-    build_status: BuildResult,
+/// Why a build was stopped before it completed
+#[derive(Debug)]
+pub enum BuildError {
+    /// a step failed and a later check aborted the build, or it was the last
+    StepFailed { step: String, log_file: PathBuf },
+    /// a check failed the build without any step failing
+    CheckFailed,
+    /// the build log couldn't be written to
+    Log {
+        log_file: PathBuf,
+        error: std::io::Error,
+    },
+    /// stopped by Ctrl-C or SIGTERM
+    Interrupted,
 }
 
-enum CheckResult<'a> {
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BuildError::StepFailed { step, log_file } => write!(
+                f,
+                "Build step `{}` failed, see the full output in {}",
+                step,
+                log_file.display()
+            ),
+            BuildError::CheckFailed => write!(f, "Build aborted by a failed check"),
+            BuildError::Log { log_file, error } => {
+                write!(
+                    f,
+                    "Unable to write build log {}: {}",
+                    log_file.display(),
+                    error
+                )
+            }
+            BuildError::Interrupted => write!(f, "Build interrupted"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+pub struct CheckContext<'a> {
+    pub src_dir: &'a Path,
+    pub install_dir: &'a Path,
+    pub build_status: BuildResult,
+}
+
+pub enum CheckResult<'a> {
     Success,
     Warning(&'a str),
     Fail,
 }
 
-enum BuildStep<'a> {
+pub enum BuildStep<'a> {
     Exec(&'a str, Vec<String>),
     Check(Box<dyn Fn(&CheckContext) -> CheckResult<'a>>),
 }
 
+/// The options given to `build` that change how the source is built
+#[derive(Debug, Default, Clone)]
+pub struct BuildFlags {
+    /// name of the build profile in the config to use
+    pub profile: Option<String>,
+    /// extra Erlang emulator types to build and install
+    pub emu_types: Vec<EmuType>,
+    /// which Erlang docs to build and install
+    pub docs: Docs,
+}
+
+/// How to build the source, from the user's build options and build profile
+#[derive(Debug, Default)]
+pub struct BuildSettings {
+    /// options passed to the build, like `./configure` flags for Erlang
+    pub options: String,
+    /// `make` targets to build instead of the language's default ones
+    pub make_targets: Vec<String>,
+    /// extra Erlang emulator types to build and install
+    pub emu_types: Vec<EmuType>,
+    /// which Erlang docs to build and install
+    pub docs: Docs,
+}
+
+/// Where the source to build comes from
+pub enum BuildSource {
+    /// a release, branch or commit downloaded from the source repo
+    Ref(GitRef),
+    /// an existing local checkout, built in place unless `out_of_tree`
+    Path { dir: PathBuf, out_of_tree: bool },
+}
+
+impl std::fmt::Display for BuildSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            BuildSource::Ref(git_ref) => write!(f, "{}", git_ref),
+            BuildSource::Path { dir, .. } => write!(f, "{}", dir.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Release,
+    Branch,
+    Commit,
+    Path,
+}
+
+/// Everything needed to repeat a build, stored with the install under `build`
+/// so `rebuild` doesn't depend on the profile or environment it was built with
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BuildRecipe {
+    pub source: SourceKind,
+    /// the release, branch, commit or path built
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// the commit the source was at, branches are rebuilt from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub out_of_tree: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    pub options: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub make_targets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub emu_types: Vec<EmuType>,
+    #[serde(default)]
+    pub docs: Docs,
+}
+
+impl BuildRecipe {
+    pub fn from_metadata(metadata: &toml::Table) -> Result<BuildRecipe> {
+        match metadata.get("build") {
+            Some(recipe) => recipe
+                .clone()
+                .try_into()
+                .wrap_err("Bad build recipe found in install"),
+            None => Err(eyre!(
+                "No build recipe found for the install, it was not built by beamup or was built by an older version"
+            )),
+        }
+    }
+
+    /// The source to build, the recorded commit instead of the branch or a
+    /// short SHA if the recipe has one
+    pub fn build_source(&self) -> BuildSource {
+        match (self.source, &self.commit) {
+            (SourceKind::Release, _) => BuildSource::Ref(GitRef::Release(self.git_ref.clone())),
+            (SourceKind::Branch | SourceKind::Commit, Some(commit)) => {
+                BuildSource::Ref(GitRef::Commit(commit.clone()))
+            }
+            (SourceKind::Branch, None) => BuildSource::Ref(GitRef::Branch(self.git_ref.clone())),
+            (SourceKind::Commit, None) => BuildSource::Ref(GitRef::Commit(self.git_ref.clone())),
+            (SourceKind::Path, _) => BuildSource::Path {
+                dir: PathBuf::from(&self.git_ref),
+                out_of_tree: self.out_of_tree,
+            },
+        }
+    }
+
+    fn settings(&self) -> BuildSettings {
+        BuildSettings {
+            options: self.options.clone(),
+            make_targets: self.make_targets.clone(),
+            emu_types: self.emu_types.clone(),
+            docs: self.docs,
+        }
+    }
+}
+
+// resolves the flags, build profile and build options of a new build into
+// the recipe to build with
+pub fn recipe<T: languages::Installable>(
+    installable: &T,
+    source: &BuildSource,
+    repo: &Option<String>,
+    flags: &BuildFlags,
+    config: &config::Config,
+) -> Result<BuildRecipe> {
+    let profile = match &flags.profile {
+        Some(name) => installable.build_profile(name, config)?,
+        None => config::BuildProfile::default(),
+    };
+
+    // the build options of a profile take precedence, otherwise maybe grab
+    // configure options from environment
+    let key = "BEAMUP_BUILD_OPTIONS";
+    let options = match (profile.build_options, env::var(key)) {
+        (Some(options), _) => options,
+        (None, Ok(options)) => options,
+        _ => installable.default_build_options(config),
+    };
+
+    let (kind, git_ref, out_of_tree) = match source {
+        BuildSource::Ref(GitRef::Release(release)) => (SourceKind::Release, release.clone(), false),
+        BuildSource::Ref(GitRef::Branch(branch)) => (SourceKind::Branch, branch.clone(), false),
+        BuildSource::Ref(GitRef::Commit(commit)) => (SourceKind::Commit, commit.clone(), false),
+        BuildSource::Path { dir, out_of_tree } => (
+            SourceKind::Path,
+            std::path::absolute(dir)?.to_string_lossy().to_string(),
+            *out_of_tree,
+        ),
+    };
+
+    Ok(BuildRecipe {
+        source: kind,
+        git_ref,
+        commit: None,
+        out_of_tree,
+        repo: repo.clone(),
+        options,
+        profile: flags.profile.clone(),
+        env: profile.env,
+        make_targets: profile.make_targets,
+        emu_types: flags.emu_types.clone(),
+        docs: flags.docs,
+    })
+}
+
 pub fn run<T: languages::Installable>(
     installable: &T,
-    git_ref: &GitRef,
+    recipe: &BuildRecipe,
     id: &String,
-    _repo: &Option<String>,
     force: bool,
     config: &config::Config,
-) -> Result<String> {
-    debug!("Building from source from git ref={git_ref} with id={id}");
+) -> Result<(String, toml::Table)> {
+    let source = recipe.build_source();
+    debug!("Building from source {source} with id={id}");
 
     let release_dir = &installable.release_dir(id)?;
-    config::maybe_create_dir(release_dir, force)?;
 
-    //maybe grab configure options from environment
-    let key = "BEAMUP_BUILD_OPTIONS";
-    let user_build_options = match env::var(key) {
-        Ok(options) => options,
-        _ => installable.default_build_options(config),
+    // check for what the build needs before downloading or creating anything
+    if env::var("BEAMUP_SKIP_PREFLIGHT").is_err() {
+        let dependencies = installable.build_dependencies(&recipe.options)?;
+        preflight(&[preflight_check(dependencies)], release_dir)?;
+    }
+
+    // on Ctrl-C the running step is killed and the build stopped, instead of
+    // beamup exiting and leaving the step running or a half created install
+    interrupt::set_handler();
+    let result = build_release(installable, recipe, &source, id, release_dir, force, config);
+    interrupt::reset_handler();
+
+    Ok((
+        release_dir.clone().into_os_string().into_string().unwrap(),
+        result?,
+    ))
+}
+
+fn build_release<T: languages::Installable>(
+    installable: &T,
+    recipe: &BuildRecipe,
+    source: &BuildSource,
+    id: &str,
+    release_dir: &Path,
+    force: bool,
+    config: &config::Config,
+) -> Result<toml::Table> {
+    // an existing install with the same id is moved aside and only removed
+    // once the new build succeeded, so a failed build doesn't lose it
+    let previous = match release_dir.try_exists() {
+        Ok(true) if force => {
+            let parent = release_dir.parent().unwrap_or(release_dir);
+            let previous = TempDir::new_in(parent, "beamup-previous")?;
+            debug!("moving existing install {release_dir:?} to {previous:?}");
+            std::fs::rename(release_dir, previous.path().join("install"))?;
+            std::fs::create_dir_all(release_dir)?;
+            Some(previous)
+        }
+        _ => {
+            config::maybe_create_dir(&release_dir.to_path_buf(), force)?;
+            None
+        }
     };
 
-    let github_repo = installable.source_repo();
-    let release = git_ref.to_string();
+    // nothing is kept of a failed or interrupted build and the install it
+    // was replacing is put back
+    let result = (|| -> Result<toml::Table> {
+        let out_dir = TempDir::new("beamup")?;
+        let (src_dir, mut metadata) = match source {
+            BuildSource::Ref(git_ref) => {
+                let github_repo = installable.source_repo(&recipe.repo, config)?;
+                let mirror = installable.mirror(&recipe.repo, config);
+                download_source(git_ref, &github_repo, mirror, out_dir.path(), config)?
+            }
+            BuildSource::Path { dir, out_of_tree } => {
+                local_source(dir, *out_of_tree, out_dir.path())?
+            }
+        };
+        interrupt::check()?;
 
-    let out_dir = TempDir::new(github_repo.repo.as_str())?;
-    let file = download_release_tarball(out_dir.path(), &github_repo, &release)?;
+        std::fs::create_dir_all(release_dir)?;
 
-    let tar_gz = File::open(&file).wrap_err_with(|| {
-        format!(
-            "Downloaded Github release tarball {} into file {:?} not found",
-            git_ref, &file
-        )
-    })?;
+        let build_steps = installable.build_steps(release_dir, &recipe.settings())?;
+        let mut build_env = installable.build_env()?;
+        build_env.extend(recipe.env.clone());
+        let log_file = installable.build_log_file(id)?;
+        build(release_dir, &src_dir, build_steps, &build_env, &log_file)?;
+
+        if let Some(name) = &recipe.profile {
+            metadata.insert("profile".to_string(), toml::Value::String(name.to_string()));
+        }
+        if !recipe.emu_types.is_empty() {
+            let emu_types = recipe
+                .emu_types
+                .iter()
+                .map(|emu_type| toml::Value::String(emu_type.as_str().to_string()))
+                .collect();
+            metadata.insert("emu_types".to_string(), toml::Value::Array(emu_types));
+        }
+        let recipe = BuildRecipe {
+            commit: match metadata.get("commit") {
+                Some(toml::Value::String(commit)) => Some(commit.clone()),
+                _ => recipe.commit.clone(),
+            },
+            ..recipe.clone()
+        };
+        metadata.insert("build".to_string(), toml::Value::try_from(recipe)?);
 
-    debug!("unpacking source tarball {tar_gz:?} to {out_dir:?}");
-    let tar = GzDecoder::new(tar_gz);
-    let mut archive = Archive::new(tar);
-    let unpack_dir = out_dir.path().join("unpack");
-    std::fs::create_dir_all(&unpack_dir)?;
-    archive.unpack(&unpack_dir)?;
+        Ok(metadata)
+    })();
+    if result.is_err() {
+        debug!("build failed, removing release directory {release_dir:?}");
+        let _ = std::fs::remove_dir_all(release_dir);
+        if let Some(previous) = &previous {
+            info!("Restoring the previous install at {release_dir:?}");
+            std::fs::rename(previous.path().join("install"), release_dir)?;
+        }
+    }
+
+    result
+}
+
+// downloads and unpacks the source tarball of the ref into `out_dir`, returning
+// the unpacked source directory and extra metadata to store with the install
+fn download_source(
+    git_ref: &GitRef,
+    github_repo: &GithubRepo,
+    mirror: Option<Mirror>,
+    out_dir: &Path,
+    config: &config::Config,
+) -> Result<(PathBuf, toml::Table)> {
+    let release = git_ref.to_string();
+    let mut metadata = toml::Table::new();
+
+    // only releases are in a mirror, branches and commits are always from Github
+    let (file, sha256) = match git_ref {
+        GitRef::Release(_) => match mirror {
+            Some(mirror) => mirror.download_release_tarball(out_dir, &release, config)?,
+            None => download_release_tarball(out_dir, github_repo, &release, config)?,
+        },
+        GitRef::Branch(_) | GitRef::Commit(_) => {
+            // download the resolved commit and not the branch so the build
+            // is of the commit recorded even if the branch moves meanwhile
+            let commit = github::resolve_commit(github_repo, &release, config)?;
+            info!("Resolved {git_ref} to commit {commit}");
+            metadata.insert("commit".to_string(), toml::Value::String(commit.clone()));
+            download_ref_tarball(out_dir, github_repo, &commit, config)?
+        }
+    };
+    metadata.insert("sha256".to_string(), toml::Value::String(sha256));
+    interrupt::check()?;
+
+    debug!("unpacking source tarball {file:?} to {out_dir:?}");
+    let unpack_dir = out_dir.join("unpack");
+    archive::unpack(&file, &unpack_dir)
+        .wrap_err_with(|| format!("Failed to unpack source tarball of {git_ref}"))?;
 
     let mut paths = std::fs::read_dir(&unpack_dir)?;
-    let binding = paths.next().unwrap()?.path();
-    let unpacked_dir: &Path = binding.as_path();
-    std::fs::create_dir_all(&release_dir)?;
-    build(&release_dir, unpacked_dir, user_build_options.as_str())?;
+    let unpacked_dir = paths.next().unwrap()?.path();
+
+    Ok((unpacked_dir, metadata))
+}
 
-    Ok(release_dir.clone().into_os_string().into_string().unwrap())
+// a local checkout is built where it is, or in a copy in `out_dir` to keep the
+// checkout clean of build artifacts
+fn local_source(dir: &Path, out_of_tree: bool, out_dir: &Path) -> Result<(PathBuf, toml::Table)> {
+    let dir = std::path::absolute(dir)?;
+    if !dir.is_dir() {
+        return Err(eyre!("Source directory {dir:?} does not exist"));
+    }
+
+    let mut metadata = toml::Table::new();
+    metadata.insert(
+        "path".to_string(),
+        toml::Value::String(dir.to_string_lossy().to_string()),
+    );
+    if let Some(commit) = git::head_commit(&dir) {
+        info!("Building {dir:?} at commit {commit}");
+        metadata.insert("commit".to_string(), toml::Value::String(commit));
+    }
+
+    if out_of_tree {
+        let build_dir = out_dir.join("build");
+        debug!("copying source {dir:?} to {build_dir:?}");
+        utils::copy_dir(&dir, &build_dir, &[".git"])
+            .wrap_err_with(|| format!("Failed to copy {dir:?} to {build_dir:?}"))?;
+        Ok((build_dir, metadata))
+    } else {
+        Ok((dir, metadata))
+    }
 }
 
-fn build(install_dir: &Path, dir: &Path, user_build_options0: &str) -> Result<()> {
-    let num_cpus = num_cpus::get().to_string();
+fn build(
+    install_dir: &Path,
+    dir: &Path,
+    build_steps: Vec<BuildStep<'static>>,
+    env: &[(String, String)],
+    log_file: &Path,
+) -> Result<(), BuildError> {
+    // the output of every step goes to the log, replacing the log of any
+    // previous build with the same id
+    if let Some(log_dir) = log_file.parent() {
+        let _ = std::fs::create_dir_all(log_dir);
+    }
+    let log = File::create(log_file).map_err(|e| BuildError::Log {
+        log_file: log_file.to_path_buf(),
+        error: e,
+    })?;
+    debug!("writing build log to {log_file:?}");
+
     let spinner_style = ProgressStyle::default_spinner()
         .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
         .template("{prefix:.bold.dim} {spinner} {wide_msg}")
@@ -103,107 +472,30 @@ fn build(install_dir: &Path, dir: &Path, user_build_options0: &str) -> Result<()
     pb.set_style(spinner_style);
     pb.enable_steady_tick(Duration::from_millis(100));
 
-    // split the configure options into a vector of String in a shell sensitive way
-    // eg.
-    //  from:
-    //      user_build_options0: --without-wx --without-observer --without-odbc --without-debugger --without-et --enable-builtin-zlib --without-javac CFLAGS="-g -O2 -march=native"
-    //  to:
-    //      user_build_options: ["--without-wx", "--without-observer", "--without-odbc", "--without-debugger", "--without-et", "--enable-builtin-zlib", "--without-javac", "CFLAGS=-g -O2 -march=native"]
-    let mut user_build_options: Vec<String> = shell_words::split(user_build_options0)?;
-    // basic configure options must always include a prefix
-    let mut build_options = vec![
-        "--prefix".to_string(),
-        install_dir.to_str().unwrap().to_string(),
-    ];
-    // append the user defined options
-    build_options.append(&mut user_build_options);
-
-    // declare the build pipeline steps
-    let build_steps: [BuildStep; 6] = [
-        BuildStep::Exec("./configure", build_options),
-        BuildStep::Check(Box::new(|context| {
-            if has_openssl(context.src_dir) {
-                CheckResult::Success
-            } else {
-                CheckResult::Warning("No usable OpenSSL found, please specify one with --with-ssl configure option, `crypto` application will not work in current build")
-            }
-        })),
-        BuildStep::Exec("make", vec!["-j".to_string(), num_cpus.to_string()]),
-        BuildStep::Exec(
-            "make",
-            vec![
-                "-j".to_string(),
-                num_cpus.to_string(),
-                "docs".to_string(),
-                "DOC_TARGETS=chunks".to_string(),
-            ],
-        ),
-        // after `make` we'll already know if this build failed or not, this allows us
-        // to make a better decision in wether to delete the installation dir should there
-        // be one.
-        BuildStep::Check(Box::new(|context| {
-            match context.build_status {
-                BuildResult::Fail => {
-                    debug!("build has failed, aborting install to prevent overwriting a possibly working installation dir");
-                    // this build has failed, we won't touch the previously existing install
-                    // dir, for all we know it could hold a previously working installation
-                    CheckResult::Fail
-                }
-                // if the build succeeded, then we check for an already existing
-                // install dir, if we find one we can delete it and proceed to the
-                // install phase
-                BuildResult::Success => {
-                    // is install dir empty? courtesy of StackOverflow
-                    let is_empty = context
-                        .install_dir
-                        .read_dir()
-                        .map(|mut i| i.next().is_none())
-                        .unwrap_or(false);
-                    if is_empty {
-                        // it's fine, it was probably us who created the dir just a moment ago,
-                        // that's why it's empty
-                        CheckResult::Success
-                    } else {
-                        debug!("found a non empty installation dir after a successful build, removing it");
-                        // dir is not empty, maybe a working installation is already there,
-                        // delete the whole thing and proceed, we can go ahead with this
-                        // because we know we have a working build in our hands
-                        let _ = std::fs::remove_dir_all(context.install_dir);
-                        CheckResult::Success
-                    }
-                }
-            }
-        })),
-        BuildStep::Exec(
-            "make",
-            vec![
-                "-j".to_string(),
-                num_cpus.to_string(),
-                "install".to_string(),
-            ],
-        ),
-        // needs to skip if no ex_doc found and give an info message
-        // BuildStep::Exec(
-        //     "make",
-        //     vec![
-        //         "-j".to_string(),
-        //         num_cpus.to_string(),
-        //         "install-docs".to_string(),
-        //     ],
-        // ),
-    ];
     // execute them sequentially
     let mut build_status = BuildResult::Success;
+    let mut failed_step = None;
     for step in build_steps.iter() {
-        let step_started = Instant::now();
+        if interrupt::interrupted() {
+            pb.finish_and_clear();
+            return Err(BuildError::Interrupted);
+        }
 
         match step {
             BuildStep::Exec(command, args) => {
                 // it only takes one exec command to fail for the build status
                 // to be fail as well, a subsequent check build step can optionally decide
                 // to fail the pipeline
-                if let BuildResult::Fail = exec(command, args, dir, step_started, &pb) {
-                    build_status = BuildResult::Fail;
+                match exec(command, args, dir, env, &log, log_file, &pb) {
+                    Ok(BuildResult::Success) => (),
+                    Ok(BuildResult::Fail) => {
+                        build_status = BuildResult::Fail;
+                        failed_step.get_or_insert(format!("{} {}", command, args.join(" ")));
+                    }
+                    Err(e) => {
+                        pb.finish_and_clear();
+                        return Err(e);
+                    }
                 }
             }
             BuildStep::Check(fun) => {
@@ -224,58 +516,260 @@ fn build(install_dir: &Path, dir: &Path, user_build_options0: &str) -> Result<()
                     CheckResult::Fail => {
                         // abort
                         pb.finish_and_clear();
-                        std::process::exit(1);
+                        return Err(match failed_step {
+                            Some(step) => BuildError::StepFailed {
+                                step,
+                                log_file: log_file.to_path_buf(),
+                            },
+                            None => BuildError::CheckFailed,
+                        });
                     }
                 }
             }
         }
     }
+    pb.finish_and_clear();
+
+    // a step after the last check, like `make install`, failing still fails
+    // the build
+    match failed_step {
+        Some(step) => Err(BuildError::StepFailed {
+            step,
+            log_file: log_file.to_path_buf(),
+        }),
+        None => Ok(()),
+    }
+}
+
+// runs the check steps meant to run before there is any source, so they are
+// given the current dir as the source dir
+fn preflight(checks: &[BuildStep<'static>], install_dir: &Path) -> Result<()> {
+    let src_dir = env::current_dir()?;
+    let context = CheckContext {
+        src_dir: &src_dir,
+        install_dir,
+        build_status: BuildResult::Success,
+    };
+
+    for check in checks.iter() {
+        if let BuildStep::Check(fun) = check {
+            match fun(&context) {
+                CheckResult::Success => (),
+                CheckResult::Warning(warning) => warn!("{}", warning),
+                CheckResult::Fail => return Err(eyre!("Preflight checks failed")),
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn has_openssl(src_dir: &Path) -> bool {
-    // check that lib/crypto/SKIP doesn't exist,
-    // if it does it means something went wrong with OpenSSL
-    !src_dir.join("./lib/crypto/SKIP").exists()
+// checks all the dependencies at once so the user gets a single list of what
+// to install, with the command to install them if the distro is known
+pub fn preflight_check(dependencies: Vec<Dependency>) -> BuildStep<'static> {
+    BuildStep::Check(Box::new(move |_| {
+        let missing: Vec<&Dependency> = dependencies
+            .iter()
+            .filter(|dependency| !dependency.is_installed())
+            .collect();
+        if missing.is_empty() {
+            return CheckResult::Success;
+        }
+
+        let mut message = "Missing build dependencies:".to_string();
+        for dependency in missing.iter() {
+            message.push_str(&format!("\n  - {}", dependency.name()));
+        }
+
+        let distro = Distro::detect();
+        let mut packages: Vec<&str> = vec![];
+        for package in missing.iter().filter_map(|d| d.package(distro)) {
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+        let needs_xcode = missing.iter().any(|d| {
+            matches!(
+                d,
+                Dependency::CCompiler | Dependency::Make | Dependency::Perl
+            )
+        });
+        if distro == Distro::MacOs && needs_xcode {
+            message.push_str("\nA C compiler, make and perl come with the Xcode command line tools: xcode-select --install");
+        }
+        if !packages.is_empty() {
+            if let Some(command) = distro.install_command(&packages) {
+                message.push_str(&format!("\nInstall them with: {command}"));
+            }
+        }
+        message.push_str("\nSet BEAMUP_SKIP_PREFLIGHT to build anyway");
+
+        error!("{}", message);
+        CheckResult::Fail
+    }))
+}
+
+// the steps of a language built with just `make` and `make install PREFIX=`,
+// with the user build options passed to both as variables
+pub fn make_steps(install_dir: &Path, settings: &BuildSettings) -> Result<Vec<BuildStep<'static>>> {
+    let user_build_options: Vec<String> = shell_words::split(&settings.options)?;
+
+    let mut install_options = vec![
+        "install".to_string(),
+        format!("PREFIX={}", install_dir.to_str().unwrap()),
+    ];
+    install_options.append(&mut user_build_options.clone());
+
+    let mut steps = if settings.make_targets.is_empty() {
+        vec![BuildStep::Exec("make", user_build_options)]
+    } else {
+        settings
+            .make_targets
+            .iter()
+            .map(|target| {
+                let mut options = vec![target.clone()];
+                options.extend(user_build_options.iter().cloned());
+                BuildStep::Exec("make", options)
+            })
+            .collect()
+    };
+    steps.push(check_build_status());
+    steps.push(BuildStep::Exec("make", install_options));
+
+    Ok(steps)
+}
+
+// after `make` we'll already know if this build failed or not, this allows us
+// to make a better decision in wether to delete the installation dir should there
+// be one.
+pub fn check_build_status() -> BuildStep<'static> {
+    BuildStep::Check(Box::new(|context| {
+        match context.build_status {
+            BuildResult::Fail => {
+                debug!("build has failed, aborting install to prevent overwriting a possibly working installation dir");
+                // this build has failed, we won't touch the previously existing install
+                // dir, for all we know it could hold a previously working installation
+                CheckResult::Fail
+            }
+            // if the build succeeded, then we check for an already existing
+            // install dir, if we find one we can delete it and proceed to the
+            // install phase
+            BuildResult::Success => {
+                // is install dir empty? courtesy of StackOverflow
+                let is_empty = context
+                    .install_dir
+                    .read_dir()
+                    .map(|mut i| i.next().is_none())
+                    .unwrap_or(false);
+                if is_empty {
+                    // it's fine, it was probably us who created the dir just a moment ago,
+                    // that's why it's empty
+                    CheckResult::Success
+                } else {
+                    debug!(
+                        "found a non empty installation dir after a successful build, removing it"
+                    );
+                    // dir is not empty, maybe a working installation is already there,
+                    // delete the whole thing and proceed, we can go ahead with this
+                    // because we know we have a working build in our hands
+                    let _ = std::fs::remove_dir_all(context.install_dir);
+                    CheckResult::Success
+                }
+            }
+        }
+    }))
 }
 
 fn exec(
     command: &str,
     args: &Vec<String>,
     dir: &Path,
-    started_ts: Instant,
+    env: &[(String, String)],
+    mut log: &File,
+    log_file: &Path,
     pb: &ProgressBar,
-) -> BuildResult {
+) -> Result<BuildResult, BuildError> {
+    let started_ts = Instant::now();
     debug!("Running {} {:?}", command, args);
     pb.set_message(format!("{} {}", command, args.join(" ")));
-    match Command::new(command).args(args).current_dir(dir).output() {
+    let _ = writeln!(log, "==> {} {}", command, args.join(" "));
+
+    // stdout and stderr share the log file so their output stays interleaved
+    let log_error = |e| BuildError::Log {
+        log_file: log_file.to_path_buf(),
+        error: e,
+    };
+    let stdout = log.try_clone().map_err(log_error)?;
+    let stderr = log.try_clone().map_err(log_error)?;
+
+    let mut cmd = Command::new(command);
+    cmd.args(args)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .current_dir(dir)
+        .stdout(Stdio::from(stdout))
+        .stderr(Stdio::from(stderr));
+    // run the step in its own process group so all of it, like the compilers
+    // started by `make`, can be killed on an interrupt
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+    let status = match cmd.spawn() {
         Err(e) => {
+            let _ = writeln!(log, "==> failed to run {}: {}", command, e);
             pb.println(format!(" {} {} {}", FAIL, command, args.join(" ")));
             error!("build failed: {}", e);
-            BuildResult::Fail
+            return Ok(BuildResult::Fail);
         }
-        Ok(output) => {
-            debug!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-            debug!("stderr: {}", String::from_utf8_lossy(&output.stderr));
-
-            match output.status.success() {
-                true => {
-                    pb.println(format!(
-                        " {} {} {} (done in {})",
-                        CHECKMARK,
-                        command,
-                        args.join(" "),
-                        HumanDuration(started_ts.elapsed())
-                    ));
-                    BuildResult::Success
-                }
-                false => {
-                    error!("stdout: {}", String::from_utf8_lossy(&output.stdout));
+        Ok(mut child) => loop {
+            if interrupt::interrupted() {
+                kill(&mut child);
+                let _ = writeln!(log, "==> {} interrupted", command);
+                pb.println(format!(" {} {} {}", FAIL, command, args.join(" ")));
+                return Err(BuildError::Interrupted);
+            }
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => thread::sleep(Duration::from_millis(100)),
+                Err(e) => {
+                    kill(&mut child);
                     pb.println(format!(" {} {} {}", FAIL, command, args.join(" ")));
-                    BuildResult::Fail
+                    error!("build failed: {}", e);
+                    return Ok(BuildResult::Fail);
                 }
             }
+        },
+    };
+
+    match status.success() {
+        true => {
+            pb.println(format!(
+                " {} {} {} (done in {})",
+                CHECKMARK,
+                command,
+                args.join(" "),
+                HumanDuration(started_ts.elapsed())
+            ));
+            Ok(BuildResult::Success)
+        }
+        false => {
+            let _ = writeln!(log, "==> {} failed with {}", command, status);
+            pb.println(format!(" {} {} {}", FAIL, command, args.join(" ")));
+            Ok(BuildResult::Fail)
         }
     }
 }
+
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    // the child is the leader of its process group, so its pid is the group's id
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.wait();
+}
+
+#[cfg(windows)]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -0,0 +1,17 @@
+use crate::languages::{Installable, Language};
+use color_eyre::eyre::{eyre, Result};
+use std::fs;
+
+pub fn run(language: &Language, id: &str) -> Result<()> {
+    let log_file = language.build_log_file(id)?;
+
+    match fs::read_to_string(&log_file) {
+        Ok(log) => {
+            print!("{log}");
+            Ok(())
+        }
+        Err(_) => Err(eyre!(
+            "No build log found for {language:?} id {id} at {log_file:?}"
+        )),
+    }
+}
@@ -0,0 +1,247 @@
+use crate::archive;
+use crate::cmd;
+use crate::components;
+use crate::config;
+use crate::config::Tool;
+use crate::languages::Installable;
+use crate::links;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use tempdir::TempDir;
+
+static MANIFEST_FILE: &str = "bundle.toml";
+
+/// An install to bundle, given as `<language or component>:<id>`
+#[derive(Debug, Clone)]
+pub struct BundleInstall {
+    pub tool: Tool,
+    pub id: String,
+}
+
+impl std::str::FromStr for BundleInstall {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((tool, id)) if !id.is_empty() => {
+                check_id(id)?;
+                Ok(BundleInstall {
+                    tool: tool.parse()?,
+                    id: id.to_string(),
+                })
+            }
+            _ => Err(format!("expected <language or component>:<id>, got {s}")),
+        }
+    }
+}
+
+// ids and releases become directory names, so one from a hostile bundle must
+// not be able to point anywhere but its own directory
+fn check_id(id: &str) -> Result<(), String> {
+    let mut components = Path::new(id).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), None) if name == id => Ok(()),
+        _ => Err(format!(
+            "{id:?} is not a valid install id, it must be a single directory name"
+        )),
+    }
+}
+
+impl std::fmt::Display for BundleInstall {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.tool, self.id)
+    }
+}
+
+// the bundle starts with the manifest, followed by the release directory of
+// each install under `<tool>/<id>`
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    installs: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ManifestEntry {
+    tool: String,
+    id: String,
+    // the install's config entry, without the `dir` it is at on this machine
+    metadata: toml::Table,
+}
+
+pub fn export(installs: &[BundleInstall], output: &Path, config: &config::Config) -> Result<()> {
+    let mut manifest = Manifest::default();
+    let mut dirs = vec![];
+    for install in installs {
+        let mut metadata = match &install.tool {
+            Tool::Language(language) => {
+                config::lookup_install_metadata(language, &install.id, config)
+            }
+            Tool::Component(kind) => {
+                config::lookup_component_install_metadata(kind, &install.id, config)
+            }
+        }
+        .wrap_err_with(|| format!("Unable to bundle {install}"))?;
+
+        let dir = match metadata.remove("dir") {
+            Some(toml::Value::String(dir)) => PathBuf::from(dir),
+            _ => return Err(eyre!("No directory found for install {install}")),
+        };
+        // a linked install is copied into the bundle like any other
+        metadata.remove("kind");
+
+        manifest.installs.push(ManifestEntry {
+            tool: install.tool.to_string(),
+            id: install.id.clone(),
+            metadata,
+        });
+        dirs.push((format!("{}/{}", install.tool, install.id), dir));
+    }
+
+    let file = File::create(output).wrap_err_with(|| format!("Failed to create {output:?}"))?;
+    let encoder = zstd::Encoder::new(file, 0)?.auto_finish();
+    let mut builder = tar::Builder::new(encoder);
+    // installs have symlinks, like Erlang's `bin` dir, that must stay links
+    builder.follow_symlinks(false);
+
+    let manifest = toml::to_string(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_FILE, manifest.as_bytes())?;
+
+    for (path, dir) in dirs {
+        debug!("adding {dir:?} to bundle as {path}");
+        builder
+            .append_dir_all(&path, &dir)
+            .wrap_err_with(|| format!("Failed to add {dir:?} to bundle"))?;
+    }
+    builder.into_inner()?;
+
+    Ok(())
+}
+
+// the bundle is unpacked next to the release directories so each install
+// can be moved into place instead of copied
+pub fn import(
+    file: &Path,
+    force: bool,
+    config_file: String,
+    config: config::Config,
+) -> Result<Vec<BundleInstall>> {
+    let data_dir = config::data_dir()?.join("beamup");
+    fs::create_dir_all(&data_dir)?;
+    let staging_dir = TempDir::new_in(&data_dir, "bundle")?;
+
+    archive::unpack(file, staging_dir.path())
+        .wrap_err_with(|| format!("Failed to unpack bundle {file:?}"))?;
+
+    let manifest_file = staging_dir.path().join(MANIFEST_FILE);
+    let manifest: Manifest = toml::from_str(
+        &fs::read_to_string(&manifest_file)
+            .wrap_err_with(|| format!("No {MANIFEST_FILE} found in bundle {file:?}"))?,
+    )?;
+    // a component's release directory is named after its release, not its id
+    for entry in manifest.installs.iter() {
+        check_id(&entry.id)
+            .and_then(
+                |_| match (entry.tool.parse(), entry.metadata.get("release")) {
+                    (Ok(Tool::Component(_)), Some(toml::Value::String(release))) => {
+                        check_id(release)
+                    }
+                    _ => Ok(()),
+                },
+            )
+            .map_err(|e| eyre!("Invalid install in bundle {file:?}: {e}"))?;
+    }
+
+    let mut config = config;
+    let mut imported = vec![];
+    for ManifestEntry { tool, id, metadata } in manifest.installs {
+        let tool: Tool = tool.parse().map_err(|e: String| eyre!(e))?;
+        let release = match metadata.get("release") {
+            Some(toml::Value::String(release)) => release.clone(),
+            _ => id.clone(),
+        };
+        let unpacked_dir = staging_dir.path().join(tool.to_string()).join(&id);
+
+        match &tool {
+            Tool::Language(language) => {
+                let release_dir = language.release_dir(&id)?;
+                move_into_place(&unpacked_dir, &release_dir, force)?;
+                config::add_install(
+                    language,
+                    &id,
+                    &release,
+                    release_dir.to_string_lossy().to_string(),
+                    metadata,
+                    config_file.clone(),
+                    config,
+                )?;
+                config = config::read_config(config_file.clone());
+                cmd::update_links::run(Some(language), &config)?;
+            }
+            Tool::Component(kind) => {
+                let c = components::Component::new(kind.clone(), &release)?;
+                move_into_place(&unpacked_dir, &c.release_dir, force)?;
+                let (bins, _): (Vec<String>, Vec<components::Kind>) = c.bins.into_iter().unzip();
+                let bin_dir = config::bin_dir();
+                let _ = fs::create_dir_all(&bin_dir);
+                links::update(bins.into_iter(), &bin_dir)?;
+                config::add_component_install(
+                    kind,
+                    &id,
+                    &release,
+                    c.release_dir.to_string_lossy().to_string(),
+                    metadata,
+                    config_file.clone(),
+                    config,
+                )?;
+                config = config::read_config(config_file.clone());
+            }
+        }
+
+        imported.push(BundleInstall { tool, id });
+    }
+
+    Ok(imported)
+}
+
+fn move_into_place(unpacked_dir: &Path, release_dir: &PathBuf, force: bool) -> Result<()> {
+    if !unpacked_dir.is_dir() {
+        return Err(eyre!("Bundle is missing the directory {unpacked_dir:?}"));
+    }
+
+    config::maybe_create_dir(release_dir, force)?;
+    // `maybe_create_dir` leaves an empty directory to be replaced
+    fs::remove_dir(release_dir)?;
+    fs::rename(unpacked_dir, release_dir)
+        .wrap_err_with(|| format!("Failed to move {unpacked_dir:?} to {release_dir:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bundle_installs() {
+        let install: BundleInstall = "gleam:1.0.0".parse().unwrap();
+        assert_eq!(install.to_string(), "gleam:1.0.0");
+
+        for bad in [
+            "gleam",
+            "gleam:",
+            "gleam:..",
+            "gleam:../1.0.0",
+            "gleam:1.0.0/",
+            "gleam:/tmp/victim",
+            "gleam:a/b",
+        ] {
+            assert!(bad.parse::<BundleInstall>().is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,11 @@
+use crate::cache;
+use crate::config;
+use color_eyre::eyre::Result;
+
+pub fn ls(config: &config::Config) -> Result<()> {
+    cache::print_downloads(config)
+}
+
+pub fn clean() -> Result<()> {
+    cache::clean()
+}
@@ -0,0 +1,7 @@
+use crate::components::Kind;
+use crate::config;
+use color_eyre::eyre::Result;
+
+pub fn run(kind: &Kind, id: &str, config_file: String, config: config::Config) -> Result<()> {
+    config::set_component_default(kind, id, config_file, config)
+}
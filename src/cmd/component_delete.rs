@@ -0,0 +1,80 @@
+use crate::components::Kind;
+use crate::config;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use std::fs;
+use std::path::Path;
+
+pub fn run(
+    kind: &Kind,
+    id: &str,
+    force: bool,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    // component installs are named by release, not id, so the directory
+    // recorded in the config is the one to remove, unless another id has it too
+    let dir = config::lookup_component_install(kind, id, &config)?;
+
+    if config::local_id(kind.to_string()).as_deref() == Some(id) && !force {
+        return Err(eyre!(
+            "Install {id} is used by the .beamup.toml in the current directory. Use `-f` to delete it anyway."
+        ));
+    }
+
+    let others: Vec<String> = config::component_install_ids_by_dir(kind, &dir, &config)
+        .into_iter()
+        .filter(|other| other != id)
+        .collect();
+    let release_dir = Path::new(&dir);
+    if !others.is_empty() {
+        info!(
+            "Keeping release directory {release_dir:?}, it is also used by {}",
+            others.join(", ")
+        );
+    } else if release_dir.exists() {
+        debug!("removing release directory {release_dir:?}");
+        fs::remove_dir_all(release_dir)
+            .wrap_err_with(|| format!("Failed to remove release directory {release_dir:?}"))?;
+    }
+
+    config::delete_component_install(kind, id, config_file, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn keeps_release_dir_used_by_another_id() {
+        let dir = TempDir::new("beamup-test").unwrap();
+        let release_dir = dir.path().join("rebar3").join("3.24.0");
+        fs::create_dir_all(&release_dir).unwrap();
+        let release_dir = release_dir.to_string_lossy().to_string();
+        let config_file = dir.path().join("config.toml").to_string_lossy().to_string();
+        let config: config::Config = toml::from_str(&format!(
+            r#"
+install_dir = ""
+
+[rebar3.installs.a]
+dir = "{release_dir}"
+release = "3.24.0"
+
+[rebar3.installs.b]
+dir = "{release_dir}"
+release = "3.24.0"
+"#
+        ))
+        .unwrap();
+
+        run(&Kind::Rebar3, "a", false, config_file.clone(), config).unwrap();
+        let config = config::read_config(config_file.clone());
+        assert!(config::lookup_component_install(&Kind::Rebar3, "a", &config).is_err());
+        assert!(Path::new(&release_dir).is_dir());
+
+        run(&Kind::Rebar3, "b", false, config_file.clone(), config).unwrap();
+        let config = config::read_config(config_file);
+        assert!(config::lookup_component_install(&Kind::Rebar3, "b", &config).is_err());
+        assert!(!Path::new(&release_dir).exists());
+    }
+}
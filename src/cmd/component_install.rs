@@ -1,19 +1,21 @@
+use crate::archive;
+use crate::archive::Format;
 use crate::components;
+use crate::config;
 use crate::github;
 use crate::utils;
 use color_eyre::{eyre::Report, eyre::Result, eyre::WrapErr};
-use flate2::read::GzDecoder;
 use std::fs;
 use std::path::PathBuf;
-use tar::Archive;
 use tempdir::TempDir;
-use zip;
 
 pub fn run(
     c: &components::Component,
-    release: &String,
+    release: &str,
+    repo: &Option<String>,
     force: bool,
-) -> Result<String, Report> {
+    config: &config::Config,
+) -> Result<(String, toml::Table), Report> {
     utils::check_release_dir(&c.release_dir, force)?;
     let release_dir_string = c
         .release_dir
@@ -22,38 +24,24 @@ pub fn run(
         .into_string()
         .unwrap();
     let asset_name = &c.asset_prefix;
-    let github_repo = &c.repo;
-    let out_dir = TempDir::new(github_repo.repo.as_str())?;
-    let file = github::download_asset(asset_name, out_dir.path(), github_repo, release)?;
+    let out_dir = TempDir::new("beamup")?;
+    let (file, sha256) = match c.mirror(repo, config) {
+        Some(mirror) => mirror.download_asset(asset_name, out_dir.path(), release, config)?,
+        None => {
+            let github_repo = &c.github_repo(repo, config)?;
+            github::download_asset(asset_name, out_dir.path(), github_repo, release, config)?
+        }
+    };
     debug!("file {:?} downloaded", file);
-    let open_file = fs::File::open(&file).wrap_err_with(|| {
-        format!(
-            "Downloaded Github asset for release {} into file {:?} not found",
-            release, &file
-        )
-    })?;
+    let format = archive::detect(&file)?;
 
     utils::maybe_create_release_dir(&c.release_dir, force)?;
 
-    // TODO: better ways to check the type than the extension
-    let ext = file.extension().map_or("", |e| e.to_str().unwrap_or(""));
-    match ext {
-        "zip" => {
-            let mut archive = zip::ZipArchive::new(open_file)?;
-            let release_dir = match c.kind {
-                components::Kind::Elp => c.release_dir.join("bin"),
-                _ => c.release_dir.clone(),
-            };
-            archive.extract(&release_dir)?;
-            Ok(release_dir_string)
-        }
-        "gz" => {
-            let tar = GzDecoder::new(open_file);
-            let mut archive = Archive::new(tar);
-            archive.unpack(&c.release_dir.join("bin"))?;
-            Ok(release_dir_string)
-        }
-        _ => {
+    let mut metadata = toml::Table::new();
+    metadata.insert("sha256".to_string(), toml::Value::String(sha256));
+
+    match format {
+        Format::Other | Format::WindowsExecutable => {
             // no unpacking needed, just copy to bin dir and make sure its executable
             let install_file = &c.release_dir.join("bin").join(file.file_name().unwrap());
             let _ = std::fs::create_dir_all(c.release_dir.join("bin"));
@@ -67,7 +55,17 @@ pub fn run(
 
             set_permissions(install_file)?;
 
-            Ok(release_dir_string)
+            Ok((release_dir_string, metadata))
+        }
+        _ => {
+            // zips of components other than ELP have their own `bin` dir,
+            // tarballs and ELP's zips only have the executables
+            let unpack_dir = match format {
+                Format::Zip if c.kind != components::Kind::Elp => c.release_dir.clone(),
+                _ => c.release_dir.join("bin"),
+            };
+            archive::unpack_release(&file, &unpack_dir, &c.release_dir)?;
+            Ok((release_dir_string, metadata))
         }
     }
 }
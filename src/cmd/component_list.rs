@@ -0,0 +1,5 @@
+use crate::config;
+
+pub fn run(config: &config::Config) {
+    config::print_component_ids(config);
+}
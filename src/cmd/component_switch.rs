@@ -0,0 +1,7 @@
+use crate::components::Kind;
+use crate::config;
+use color_eyre::eyre::Result;
+
+pub fn run(kind: &Kind, id: &str, config: config::Config) -> Result<()> {
+    config::switch_component(kind, id, &config)
+}
@@ -0,0 +1,35 @@
+use crate::config;
+use crate::languages::{Installable, Language};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use std::fs;
+
+pub fn run(
+    language: &Language,
+    id: &str,
+    force: bool,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    // we just look it up to return an error if it doesn't exist
+    let _ = config::lookup_install(language, id, &config)?;
+
+    if config::local_id(language.to_string()).as_deref() == Some(id) && !force {
+        return Err(eyre!(
+            "Install {id} is used by the .beamup.toml in the current directory. Use `-f` to delete it anyway."
+        ));
+    }
+
+    let release_dir = language.release_dir(id)?;
+    if release_dir.exists() {
+        debug!("removing release directory {release_dir:?}");
+        fs::remove_dir_all(&release_dir)
+            .wrap_err_with(|| format!("Failed to remove release directory {release_dir:?}"))?;
+    }
+
+    let log_file = language.build_log_file(id)?;
+    if log_file.exists() {
+        let _ = fs::remove_file(&log_file);
+    }
+
+    config::delete_install(language, id, config_file, config)
+}
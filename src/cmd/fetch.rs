@@ -0,0 +1,23 @@
+use crate::config;
+use crate::git;
+use crate::languages;
+use color_eyre::eyre::Result;
+
+pub fn run<T: languages::Installable>(
+    installable: &T,
+    repo: &Option<String>,
+    config: &config::Config,
+) -> Result<()> {
+    let github_repo = installable.source_repo(repo, config)?;
+    let refs = git::fetch_refs(&github_repo, config)?;
+
+    info!(
+        "Fetched {} tags and {} branches from {}/{}",
+        refs.tags.len(),
+        refs.branches.len(),
+        github_repo.org,
+        github_repo.repo
+    );
+
+    Ok(())
+}
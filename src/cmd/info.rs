@@ -0,0 +1,38 @@
+use crate::config;
+use crate::languages::Language;
+use color_eyre::eyre::Result;
+
+pub fn run(language: &Language, id: &str, config: &config::Config) -> Result<()> {
+    let metadata = config::lookup_install_metadata(language, id, config)?;
+
+    println!("{language:?} {id}:");
+    print_table(&metadata, 1);
+
+    Ok(())
+}
+
+// nested tables, like the build recipe, are printed indented under their key
+fn print_table(table: &toml::Table, depth: usize) {
+    let indent = "  ".repeat(depth);
+    for (key, value) in table.iter() {
+        match value {
+            toml::Value::Table(table) => {
+                println!("{indent}{key}:");
+                print_table(table, depth + 1);
+            }
+            toml::Value::String(s) => println!("{indent}{key}: {s}"),
+            toml::Value::Array(values) => {
+                let values = values
+                    .iter()
+                    .map(|v| match v {
+                        toml::Value::String(s) => s.to_owned(),
+                        v => v.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("{indent}{key}: {values}");
+            }
+            v => println!("{indent}{key}: {v}"),
+        }
+    }
+}
@@ -1,62 +1,108 @@
+use crate::archive;
+use crate::archive::Format;
+use crate::cache;
+use crate::config;
 use crate::github;
 use crate::languages;
 use crate::languages::Libc;
 use crate::utils;
-use color_eyre::eyre::{eyre, Report, Result, WrapErr};
-use flate2::read::GzDecoder;
-use std::fs::File;
+use color_eyre::eyre::{eyre, Report, Result};
 use std::path::PathBuf;
-use tar::Archive;
 use tempdir::TempDir;
-use zip;
 
 #[cfg(windows)]
 use std::process::ExitStatus;
 
+/// Where the archive to install comes from
+#[derive(Debug)]
+pub enum InstallSource<'a> {
+    /// an asset of a release from Github or the mirror
+    Release {
+        release: &'a str,
+        libc: &'a Option<Libc>,
+        repo: &'a Option<String>,
+    },
+    /// a local archive, like of an internally patched build
+    File(PathBuf),
+    /// an archive downloaded from anywhere
+    Url(String),
+}
+
 pub fn run<T: languages::Installable>(
     installable: &T,
-    id: &String,
-    release: &str,
-    libc: &Option<Libc>,
+    id: &str,
+    source: &InstallSource,
     force: bool,
-) -> Result<String, Report> {
+    config: &config::Config,
+) -> Result<(String, toml::Table), Report> {
     let release_dir = &installable.release_dir(id)?;
-    utils::maybe_create_release_dir(&release_dir, force)?;
+    utils::maybe_create_release_dir(release_dir, force)?;
 
-    let github_repo = installable.binary_repo();
-    let out_dir = TempDir::new(github_repo.repo.as_str())?;
-    let asset_name = installable.asset_prefix(libc)?;
-    let file = github::download_asset(&asset_name, out_dir.path(), &github_repo, release)?;
+    let mut metadata = toml::Table::new();
+    let out_dir = TempDir::new("beamup")?;
+    let (file, sha256) = match source {
+        InstallSource::Release {
+            release,
+            libc,
+            repo,
+        } => {
+            let asset_name = installable.asset_prefix(libc)?;
+            match installable.mirror(repo, config) {
+                Some(mirror) => {
+                    mirror.download_asset(&asset_name, out_dir.path(), release, config)?
+                }
+                None => {
+                    let github_repo = installable.binary_repo(repo, config)?;
+                    github::download_asset(
+                        &asset_name,
+                        out_dir.path(),
+                        &github_repo,
+                        release,
+                        config,
+                    )?
+                }
+            }
+        }
+        InstallSource::File(file) => {
+            let file = std::path::absolute(file)?;
+            metadata.insert(
+                "source".to_string(),
+                toml::Value::String(file.to_string_lossy().to_string()),
+            );
+            let sha256 = cache::sha256_file(&file)?;
+            (file, sha256)
+        }
+        InstallSource::Url(url) => {
+            metadata.insert("source".to_string(), toml::Value::String(url.clone()));
+            // the file name is kept for the messages about it
+            let name = url
+                .split(['?', '#'])
+                .next()
+                .and_then(|url| url.rsplit('/').next())
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| eyre!("No file name found in URL {url}"))?;
+            let file = out_dir.path().join(name);
+            // with no sha256 to check a cached copy against, what the URL
+            // serves now could differ from it, so it is always downloaded
+            let sha256 = github::download(&file, url, None, format!("Downloading {name}"), config)?;
+            (file, sha256)
+        }
+    };
     debug!("file {:?} downloaded", file);
-    let open_file = File::open(&file).wrap_err_with(|| {
-        format!(
-            "Downloaded Github asset for release {} into file {:?} not found",
-            release, &file
-        )
-    })?;
 
     let extract_dir = installable.extract_dir(id)?;
 
-    // TODO: better ways to check the type than the extension
-    let ext = file.extension().map_or("", |e| e.to_str().unwrap_or(""));
-    match ext {
-        "exe" => {
-            let release_dir = release_dir.clone().into_os_string().into_string().unwrap();
-            exe_run(file, release_dir.clone())?;
-            Ok(release_dir)
-        }
-        "zip" => {
-            let mut archive = zip::ZipArchive::new(open_file)?;
-            archive.extract(extract_dir)?;
-            Ok(release_dir.clone().into_os_string().into_string().unwrap())
-        }
-        _ => {
-            let tar = GzDecoder::new(open_file);
-            let mut archive = Archive::new(tar);
-            archive.unpack(extract_dir)?;
-            Ok(release_dir.clone().into_os_string().into_string().unwrap())
+    let dir = release_dir.clone().into_os_string().into_string().unwrap();
+    match archive::detect(&file)? {
+        Format::WindowsExecutable => {
+            exe_run(file, dir.clone())?;
         }
+        _ => archive::unpack_release(&file, &extract_dir, release_dir)?,
     }
+
+    metadata.insert("sha256".to_string(), toml::Value::String(sha256));
+
+    Ok((dir, metadata))
 }
 
 #[cfg(unix)]
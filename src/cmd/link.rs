@@ -0,0 +1,82 @@
+use crate::config;
+use crate::languages;
+use crate::languages::Language;
+use color_eyre::eyre::{eyre, Result};
+use std::path::Path;
+
+// marks an install in the config as managed outside of beamup
+static LINK_KIND: &str = "link";
+
+pub fn add(
+    language: &Language,
+    id: &String,
+    path: &str,
+    force: bool,
+    config_file: String,
+    config: config::Config,
+) -> Result<String> {
+    let dir = std::path::absolute(path)?;
+    check_install_dir(language, &dir, &config)?;
+
+    // replacing an install only drops its config entry, its directory stays
+    if let Ok(existing_dir) = config::lookup_install(language, id, &config) {
+        if !force {
+            return Err(eyre!(
+                "Install {id} already exists. Use `-f` to replace it with the link."
+            ));
+        }
+        warn!("Replacing install {id}, its directory {existing_dir} is left in place");
+    }
+
+    let dir = dir.into_os_string().into_string().unwrap();
+    let mut metadata = toml::Table::new();
+    metadata.insert(
+        "kind".to_string(),
+        toml::Value::String(LINK_KIND.to_string()),
+    );
+
+    config::add_install(language, id, id, dir.clone(), metadata, config_file, config)?;
+
+    Ok(dir)
+}
+
+pub fn rm(
+    language: &Language,
+    id: &str,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    let metadata = config::lookup_install_metadata(language, id, &config)?;
+
+    match metadata.get("kind") {
+        Some(toml::Value::String(kind)) if kind == LINK_KIND => {
+            // only the config entry is removed, the linked directory isn't ours to delete
+            config::delete_install(language, id, config_file, config)
+        }
+        _ => Err(eyre!(
+            "Install {id} is not a link. Use `beamup delete {language} {id}` to delete it."
+        )),
+    }
+}
+
+// the shims run executables from `<dir>/bin` so at least one of the
+// language's executables must be found there
+fn check_install_dir(language: &Language, dir: &Path, config: &config::Config) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(eyre!("Directory {dir:?} does not exist"));
+    }
+
+    let bin_dir = dir.join("bin");
+    let found = languages::bins(config)
+        .iter()
+        .filter(|(_, l)| l == language)
+        .any(|(bin, _)| bin_dir.join(bin).exists());
+
+    if found {
+        Ok(())
+    } else {
+        Err(eyre!(
+            "No {language} executables found in {bin_dir:?}, is {dir:?} an existing {language} install?"
+        ))
+    }
+}
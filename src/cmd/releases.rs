@@ -1,7 +1,17 @@
+use crate::config;
 use crate::github::print_releases;
 use crate::languages;
+use color_eyre::eyre::Result;
+
+pub fn run<T: languages::Installable>(
+    installable: &T,
+    repo: &Option<String>,
+    config: &config::Config,
+) -> Result<()> {
+    if let Some(mirror) = installable.mirror(repo, config) {
+        return mirror.print_releases(config);
+    }
 
-pub fn run<T: languages::Installable>(installable: &T) {
     // TODO: source repo and binary repo could have different releases to print
-    print_releases(&installable.source_repo());
+    print_releases(&installable.source_repo(repo, config)?, config)
 }
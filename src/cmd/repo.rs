@@ -0,0 +1,29 @@
+use crate::config;
+use crate::github::GithubRepo;
+use color_eyre::eyre::Result;
+
+pub fn add(
+    tool: &config::Tool,
+    id: &str,
+    url: &str,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    // fail early on urls we won't be able to use later
+    let _ = GithubRepo::from_url(url)?;
+
+    config::add_repo(tool, id, url, config_file, config)
+}
+
+pub fn rm(
+    tool: &config::Tool,
+    id: &str,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    config::remove_repo(tool, id, config_file, config)
+}
+
+pub fn ls(config: &config::Config) {
+    config::print_repos(config);
+}
@@ -0,0 +1,18 @@
+use crate::config;
+use crate::git;
+use crate::languages;
+use color_eyre::eyre::Result;
+
+pub fn run<T: languages::Installable>(
+    installable: &T,
+    repo: &Option<String>,
+    config: &config::Config,
+) -> Result<()> {
+    let github_repo = installable.source_repo(repo, config)?;
+
+    for tag in git::refs(&github_repo, config)?.tags {
+        println!("{tag}");
+    }
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ pub mod elp;
 pub mod rebar3;
 use crate::config;
 use crate::github::GithubRepo;
+use crate::mirror::Mirror;
 use color_eyre::eyre::Result;
 use std::path::PathBuf;
 use strum::IntoEnumIterator;
@@ -54,6 +55,23 @@ impl Component {
             Kind::Rebar3 => rebar3::new_component(release),
         }
     }
+
+    pub fn github_repo(
+        &self,
+        repo: &Option<String>,
+        config: &config::Config,
+    ) -> Result<GithubRepo> {
+        match repo {
+            Some(id) => {
+                config::lookup_repo(&config::Tool::Component(self.kind.clone()), id, config)
+            }
+            None => Ok(self.repo.clone()),
+        }
+    }
+
+    pub fn mirror(&self, repo: &Option<String>, config: &config::Config) -> Option<Mirror> {
+        config::lookup_mirror(&config::Tool::Component(self.kind.clone()), repo, config)
+    }
 }
 
 pub fn release_dir(kind_str: String, id: &String) -> Result<PathBuf> {
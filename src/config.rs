@@ -1,20 +1,34 @@
 use crate::components;
+use crate::github::GithubRepo;
 use crate::languages;
+use crate::mirror::Mirror;
+use clap::ValueEnum;
 use color_eyre::{eyre::eyre, eyre::Report, eyre::Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
 use std::path::*;
+use strum::IntoEnumIterator;
 
 static LOCAL_CONFIG_FILE: &str = ".beamup.toml";
 static CONFIG_FILE: &str = "config.toml";
+// in megabytes
+static DEFAULT_CACHE_SIZE_LIMIT: u64 = 2048;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     install_dir: String,
+    // in megabytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_size_limit: Option<u64>,
+    // used when neither GITHUB_TOKEN nor GH_TOKEN is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    github_token: Option<String>,
     erlang: Option<LanguageConfig>,
     gleam: Option<LanguageConfig>,
     elixir: Option<LanguageConfig>,
+    lfe: Option<LanguageConfig>,
     elp: Option<ComponentConfig>,
     rebar3: Option<ComponentConfig>,
 }
@@ -24,6 +38,27 @@ pub struct LanguageConfig {
     default: Option<String>,
     default_build_options: Option<String>,
     installs: toml::Table,
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    repos: toml::Table,
+    // URL of a release index to download from instead of Github
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mirror: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    build_profiles: BTreeMap<String, BuildProfile>,
+}
+
+/// A named set of build settings under `[<language>.build_profiles.<name>]`,
+/// selected with `build --profile <name>`
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct BuildProfile {
+    /// used in place of `default_build_options`
+    pub build_options: Option<String>,
+    /// extra environment variables for the build, like `CFLAGS`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// `make` targets to build instead of the language's default ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub make_targets: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -31,6 +66,41 @@ pub struct ComponentConfig {
     default: Option<String>,
     default_build_options: Option<String>,
     installs: toml::Table,
+    #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+    repos: toml::Table,
+    // URL of a release index to download from instead of Github
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mirror: Option<String>,
+}
+
+/// A language or a component, for the parts of the config shared by both
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tool {
+    Language(languages::Language),
+    Component(components::Kind),
+}
+
+impl std::fmt::Display for Tool {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Tool::Language(language) => write!(f, "{language}"),
+            Tool::Component(kind) => write!(f, "{kind}"),
+        }
+    }
+}
+
+impl std::str::FromStr for Tool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(language) = <languages::Language as ValueEnum>::from_str(s, true) {
+            Ok(Tool::Language(language))
+        } else if let Ok(kind) = <components::Kind as ValueEnum>::from_str(s, true) {
+            Ok(Tool::Component(kind))
+        } else {
+            Err(format!("unknown language or component {s}"))
+        }
+    }
 }
 
 pub fn print_ids(config: &Config) {
@@ -42,6 +112,23 @@ pub fn print_ids(config: &Config) {
     println!();
     println!("Gleam:");
     config.gleam.as_ref().map(print_language_ids);
+    println!();
+    println!("LFE:");
+    config.lfe.as_ref().map(print_language_ids);
+}
+
+pub fn print_component_ids(config: &Config) {
+    println!("Elp:");
+    config.elp.as_ref().map(print_component_install_ids);
+    println!();
+    println!("Rebar3:");
+    config.rebar3.as_ref().map(print_component_install_ids);
+}
+
+fn print_component_install_ids(c: &ComponentConfig) {
+    for id in c.installs.keys() {
+        println!("{id}")
+    }
 }
 
 fn print_language_ids(lc: &LanguageConfig) {
@@ -55,6 +142,7 @@ pub fn get_language_config(language: &languages::Language, config: &Config) -> L
         languages::Language::Gleam => config.gleam.clone().unwrap_or_default(),
         languages::Language::Erlang => config.erlang.clone().unwrap_or_default(),
         languages::Language::Elixir => config.elixir.clone().unwrap_or_default(),
+        languages::Language::Lfe => config.lfe.clone().unwrap_or_default(),
     }
 }
 
@@ -110,6 +198,25 @@ pub fn switch(language: &languages::Language, id: &str, config: &Config) -> Resu
     Ok(())
 }
 
+pub fn switch_component(kind: &components::Kind, id: &str, config: &Config) -> Result<()> {
+    let component_config = get_component_config(kind, config);
+
+    // we just look it up to return an error if it doesn't exist
+    let _ = lookup_component_install_by_id(id.to_string(), Some(component_config))?;
+
+    let mut c = match local_config() {
+        None => toml::Table::new(),
+        Some(local_config) => local_config.clone(),
+    };
+
+    c.insert(kind.to_string(), toml::Value::String(id.to_string()));
+
+    let toml_string = toml::to_string(&c).unwrap();
+    let mut file = fs::File::create(LOCAL_CONFIG_FILE)?;
+    file.write_all(toml_string.as_bytes())?;
+    Ok(())
+}
+
 fn get_local_id(language_str: String, local_config: &Option<toml::Table>) -> Option<&toml::Value> {
     match local_config {
         None => None,
@@ -135,7 +242,7 @@ pub fn get_otp_major_vsn() -> Result<String> {
 }
 
 fn check_release_dir(releases_dir: &Path) -> Result<String> {
-    for entry in std::fs::read_dir(&releases_dir)? {
+    for entry in std::fs::read_dir(releases_dir)? {
         let entry = entry?;
         let path = entry.path();
         if path.is_dir() {
@@ -180,13 +287,76 @@ pub fn component_install_to_use(kind: &components::Kind) -> Result<String> {
     lookup_component_install_by_id(id, Some(component_config))
 }
 
+pub fn lookup_install(language: &languages::Language, id: &str, config: &Config) -> Result<String> {
+    let language_config = get_language_config(language, config);
+    lookup_install_by_id(id.to_string(), Some(language_config))
+}
+
+// the full table of an install, for the older installs that only
+// stored a directory this is a table with just `dir`
+pub fn lookup_install_metadata(
+    language: &languages::Language,
+    id: &str,
+    config: &Config,
+) -> Result<toml::Table> {
+    install_metadata(&get_language_config(language, config).installs, id)
+}
+
+pub fn lookup_component_install_metadata(
+    kind: &components::Kind,
+    id: &str,
+    config: &Config,
+) -> Result<toml::Table> {
+    install_metadata(&get_component_config(kind, config).installs, id)
+}
+
+fn install_metadata(installs: &toml::Table, id: &str) -> Result<toml::Table> {
+    match installs.get(id) {
+        None => Err(eyre!("No install found for id {id}")),
+        Some(toml::Value::String(dir)) => {
+            let mut table = toml::Table::new();
+            table.insert("dir".to_string(), toml::Value::String(dir.to_owned()));
+            Ok(table)
+        }
+        Some(toml::Value::Table(table)) => Ok(table.clone()),
+        _ => Err(eyre!("Bad entry found in installs for id {id}")),
+    }
+}
+
+pub fn lookup_component_install(
+    kind: &components::Kind,
+    id: &str,
+    config: &Config,
+) -> Result<String> {
+    let component_config = get_component_config(kind, config);
+    lookup_component_install_by_id(id.to_string(), Some(component_config))
+}
+
+// component installs are named by release, not id, so more than one id can
+// have the same directory
+pub fn component_install_ids_by_dir(
+    kind: &components::Kind,
+    dir: &str,
+    config: &Config,
+) -> Vec<String> {
+    let installs = get_component_config(kind, config).installs;
+    installs
+        .keys()
+        .filter(|id| {
+            install_metadata(&installs, id)
+                .is_ok_and(|metadata| metadata.get("dir").and_then(|d| d.as_str()) == Some(dir))
+        })
+        .cloned()
+        .collect()
+}
+
 pub fn install_to_use_by_bin(bin: &str) -> Result<String> {
     let (_, config) = home_config()?;
     let language = languages::bin_to_language(bin.to_string(), &config)?;
     install_to_use_by_language(language)
 }
 
-fn install_to_use_by_language(language: languages::Language) -> Result<String> {
+pub fn install_to_use_by_language(language: languages::Language) -> Result<String> {
     let (_, config) = home_config()?;
     let language_config = get_language_config(&language, &config);
     let local_config = local_config();
@@ -208,6 +378,7 @@ fn install_to_use_by_language(language: languages::Language) -> Result<String> {
                 languages::Language::Gleam => get_default_id(&config.gleam)?,
                 languages::Language::Erlang => get_default_id(&config.erlang)?,
                 languages::Language::Elixir => get_default_id(&config.elixir)?,
+                languages::Language::Lfe => get_default_id(&config.lfe)?,
             }
         }
         Some(id) => id.clone(),
@@ -275,6 +446,42 @@ pub fn lookup_default_build_options(language: &languages::Language, config: &Con
     }
 }
 
+pub fn lookup_build_profile(
+    language: &languages::Language,
+    name: &str,
+    config: &Config,
+) -> Result<BuildProfile> {
+    debug!("Looking up build profile {name} for {:?}", language);
+
+    let lc = get_language_config(language, config);
+
+    match lc.build_profiles.get(name) {
+        Some(profile) => Ok(profile.clone()),
+        None if lc.build_profiles.is_empty() => Err(eyre!(
+            "No build profiles found for language {:?} in config",
+            language
+        )),
+        None => Err(eyre!(
+            "Build profile {name} not found for language {:?}, available profiles: {}",
+            language,
+            lc.build_profiles
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+// size limit of the download cache in bytes
+pub fn lookup_cache_size_limit(config: &Config) -> u64 {
+    config.cache_size_limit.unwrap_or(DEFAULT_CACHE_SIZE_LIMIT) * 1024 * 1024
+}
+
+pub fn lookup_github_token(config: &Config) -> Option<String> {
+    config.github_token.clone()
+}
+
 pub fn set_default(
     language: &languages::Language,
     id: &String,
@@ -283,33 +490,36 @@ pub fn set_default(
 ) -> Result<(), Report> {
     debug!("set default {:?} to use to {:?}", language, id);
     let lc = get_language_config(language, &config);
-    let LanguageConfig {
-        default: _,
-        installs: installs_table,
-        default_build_options,
-    } = lc;
 
     let new_lc = LanguageConfig {
         default: Some(id.to_owned()),
-        installs: installs_table.clone(),
-        default_build_options: default_build_options.clone(),
+        ..lc
     };
 
-    let new_config = match language {
-        languages::Language::Gleam => Config {
-            gleam: Some(new_lc),
-            ..config
-        },
-        languages::Language::Erlang => Config {
-            erlang: Some(new_lc),
-            ..config
-        },
-        languages::Language::Elixir => Config {
-            elixir: Some(new_lc),
-            ..config
-        },
+    let new_config = set_language_config(language, new_lc, config);
+
+    write_config(config_file, new_config)
+}
+
+pub fn set_component_default(
+    kind: &components::Kind,
+    id: &str,
+    config_file: String,
+    config: Config,
+) -> Result<()> {
+    debug!("set default {:?} to use to {:?}", kind, id);
+    let c = get_component_config(kind, &config);
+
+    // we just look it up to return an error if it doesn't exist
+    let _ = lookup_component_install_by_id(id.to_string(), Some(c.clone()))?;
+
+    let new_c = ComponentConfig {
+        default: Some(id.to_owned()),
+        ..c
     };
 
+    let new_config = set_component_config(kind, new_c, config);
+
     write_config(config_file, new_config)
 }
 
@@ -318,21 +528,22 @@ pub fn update_language_config(
     id: &String,
     release: &String,
     dir: String,
+    metadata: toml::Table,
     lc: LanguageConfig,
 ) -> Result<LanguageConfig> {
-    let LanguageConfig {
-        default: _,
-        installs: mut table,
-        default_build_options,
-    } = lc;
-    let mut id_table = toml::Table::new();
+    let mut table = lc.installs.clone();
+    let mut id_table = metadata;
     id_table.insert("dir".to_string(), toml::Value::String(dir));
     id_table.insert(
         "release".to_string(),
         toml::Value::String(release.to_owned()),
     );
 
-    if language == &languages::Language::Elixir {
+    // both are compiled to BEAM files for a specific OTP version, which an
+    // imported install already has
+    if (language == &languages::Language::Elixir || language == &languages::Language::Lfe)
+        && !id_table.contains_key("otp_vsn")
+    {
         let otp_vsn = get_otp_major_vsn()?;
         id_table.insert("otp_vsn".to_string(), toml::Value::String(otp_vsn));
     }
@@ -340,8 +551,8 @@ pub fn update_language_config(
     table.insert(id.clone(), toml::Value::Table(id_table));
     Ok(LanguageConfig {
         default: Some(id.to_owned()),
-        installs: table.clone(),
-        default_build_options: default_build_options.clone(),
+        installs: table,
+        ..lc
     })
 }
 
@@ -350,14 +561,11 @@ pub fn update_component_config(
     id: &String,
     release: &String,
     dir: String,
+    metadata: toml::Table,
     c: ComponentConfig,
 ) -> Result<ComponentConfig> {
-    let ComponentConfig {
-        default: _,
-        installs: mut table,
-        default_build_options,
-    } = c;
-    let mut id_table = toml::Table::new();
+    let mut table = c.installs.clone();
+    let mut id_table = metadata;
     id_table.insert("dir".to_string(), toml::Value::String(dir));
     id_table.insert(
         "release".to_string(),
@@ -367,8 +575,8 @@ pub fn update_component_config(
     table.insert(id.clone(), toml::Value::Table(id_table));
     Ok(ComponentConfig {
         default: Some(id.to_owned()),
-        installs: table.clone(),
-        default_build_options: default_build_options.clone(),
+        installs: table,
+        ..c
     })
 }
 
@@ -377,33 +585,98 @@ pub fn add_install(
     id: &String,
     release: &String,
     dir: String,
+    metadata: toml::Table,
     config_file: String,
     config: Config,
 ) -> Result<()> {
     debug!("adding install {id} pointing to {dir}");
     let language_config = get_language_config(language, &config);
 
-    let updated_language_config =
-        update_language_config(language, id, release, dir, language_config.clone())?;
+    let updated_language_config = update_language_config(
+        language,
+        id,
+        release,
+        dir,
+        metadata,
+        language_config.clone(),
+    )?;
+
+    let new_config = set_language_config(language, updated_language_config, config);
 
-    let new_config = match language {
+    let _ = write_config(config_file, new_config);
+
+    Ok(())
+}
+
+pub fn delete_install(
+    language: &languages::Language,
+    id: &str,
+    config_file: String,
+    config: Config,
+) -> Result<()> {
+    debug!("deleting install {id}");
+    let mut language_config = get_language_config(language, &config);
+
+    if language_config.installs.remove(id).is_none() {
+        return Err(eyre!("No install found for id {id}"));
+    }
+
+    // don't leave the default pointing at an install that no longer exists
+    if language_config.default.as_deref() == Some(id) {
+        language_config.default = None;
+    }
+
+    let new_config = set_language_config(language, language_config, config);
+
+    write_config(config_file, new_config)
+}
+
+pub fn delete_component_install(
+    kind: &components::Kind,
+    id: &str,
+    config_file: String,
+    config: Config,
+) -> Result<()> {
+    debug!("deleting component install {id}");
+    let mut component_config = get_component_config(kind, &config);
+
+    if component_config.installs.remove(id).is_none() {
+        return Err(eyre!("No install found for id {id}"));
+    }
+
+    // don't leave the default pointing at an install that no longer exists
+    if component_config.default.as_deref() == Some(id) {
+        component_config.default = None;
+    }
+
+    let new_config = set_component_config(kind, component_config, config);
+
+    write_config(config_file, new_config)
+}
+
+fn set_language_config(
+    language: &languages::Language,
+    language_config: LanguageConfig,
+    config: Config,
+) -> Config {
+    match language {
         languages::Language::Gleam => Config {
-            gleam: Some(updated_language_config),
+            gleam: Some(language_config),
             ..config
         },
         languages::Language::Erlang => Config {
-            erlang: Some(updated_language_config),
+            erlang: Some(language_config),
             ..config
         },
         languages::Language::Elixir => Config {
-            elixir: Some(updated_language_config),
+            elixir: Some(language_config),
             ..config
         },
-    };
-
-    let _ = write_config(config_file, new_config);
-
-    Ok(())
+        languages::Language::Lfe => Config {
+            lfe: Some(language_config),
+            ..config
+        },
+    }
 }
 
 pub fn add_component_install(
@@ -411,6 +684,7 @@ pub fn add_component_install(
     id: &String,
     release: &String,
     dir: String,
+    metadata: toml::Table,
     config_file: String,
     config: Config,
 ) -> Result<()> {
@@ -418,22 +692,126 @@ pub fn add_component_install(
     let component_config = get_component_config(kind, &config);
 
     let updated_component_config =
-        update_component_config(kind, id, release, dir, component_config.clone())?;
+        update_component_config(kind, id, release, dir, metadata, component_config.clone())?;
+
+    let new_config = set_component_config(kind, updated_component_config, config);
+
+    let _ = write_config(config_file, new_config);
 
-    let new_config = match kind {
+    Ok(())
+}
+
+fn set_component_config(
+    kind: &components::Kind,
+    component_config: ComponentConfig,
+    config: Config,
+) -> Config {
+    match kind {
         components::Kind::Elp => Config {
-            elp: Some(updated_component_config),
+            elp: Some(component_config),
             ..config
         },
         components::Kind::Rebar3 => Config {
-            rebar3: Some(updated_component_config),
+            rebar3: Some(component_config),
             ..config
         },
+    }
+}
+
+fn get_repos(tool: &Tool, config: &Config) -> toml::Table {
+    match tool {
+        Tool::Language(language) => get_language_config(language, config).repos,
+        Tool::Component(kind) => get_component_config(kind, config).repos,
+    }
+}
+
+fn set_repos(tool: &Tool, repos: toml::Table, config: Config) -> Config {
+    match tool {
+        Tool::Language(language) => {
+            let lc = LanguageConfig {
+                repos,
+                ..get_language_config(language, &config)
+            };
+            set_language_config(language, lc, config)
+        }
+        Tool::Component(kind) => {
+            let c = ComponentConfig {
+                repos,
+                ..get_component_config(kind, &config)
+            };
+            set_component_config(kind, c, config)
+        }
+    }
+}
+
+pub fn add_repo(
+    tool: &Tool,
+    id: &str,
+    url: &str,
+    config_file: String,
+    config: Config,
+) -> Result<()> {
+    debug!("adding repo {id} for {tool} pointing to {url}");
+    let mut repos = get_repos(tool, &config);
+    repos.insert(id.to_string(), toml::Value::String(url.to_string()));
+
+    write_config(config_file, set_repos(tool, repos, config))
+}
+
+pub fn remove_repo(tool: &Tool, id: &str, config_file: String, config: Config) -> Result<()> {
+    debug!("removing repo {id} for {tool}");
+    let mut repos = get_repos(tool, &config);
+    if repos.remove(id).is_none() {
+        return Err(eyre!("No repo {id} found for {tool}"));
+    }
+
+    write_config(config_file, set_repos(tool, repos, config))
+}
+
+// the `--repo` given to a command is the id of an entry in the tool's `repos`,
+// used instead of its upstream repo and of any mirror
+pub fn lookup_repo(tool: &Tool, id: &str, config: &Config) -> Result<GithubRepo> {
+    debug!("Looking up repo {id} for {tool}");
+    match get_repos(tool, config).get(id) {
+        Some(toml::Value::String(url)) => GithubRepo::from_url(url),
+        Some(_) => Err(eyre!("Bad url found in repos of {tool} for id {id}")),
+        None => Err(eyre!(
+            "No repo {id} found for {tool}. Add one with `beamup repo add {tool} {id} <url>`"
+        )),
+    }
+}
+
+pub fn lookup_mirror(tool: &Tool, repo: &Option<String>, config: &Config) -> Option<Mirror> {
+    if repo.is_some() {
+        return None;
+    }
+
+    let mirror = match tool {
+        Tool::Language(language) => get_language_config(language, config).mirror,
+        Tool::Component(kind) => get_component_config(kind, config).mirror,
     };
+    debug!("mirror for {tool}: {mirror:?}");
 
-    let _ = write_config(config_file, new_config);
+    mirror.map(|url| Mirror::new(&url))
+}
 
-    Ok(())
+pub fn print_repos(config: &Config) {
+    let tools = languages::Language::iter()
+        .map(Tool::Language)
+        .chain(components::Kind::iter().map(Tool::Component));
+
+    for (i, tool) in tools.enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match &tool {
+            Tool::Language(language) => println!("{language:?}:"),
+            Tool::Component(kind) => println!("{kind:?}:"),
+        }
+        for (id, url) in get_repos(&tool, config).iter() {
+            println!("{id} {}", url.as_str().unwrap_or_default())
+        }
+    }
 }
 
 pub fn maybe_create_dir(release_dir: &PathBuf, force: bool) -> Result<()> {
@@ -493,31 +871,14 @@ pub fn home_config_file() -> Result<String> {
     if !default_config.exists() {
         let config = Config {
             install_dir: default_data.to_str().unwrap().to_string(),
-            erlang: Some(LanguageConfig {
-                default: None,
-                installs: toml::Table::new(),
-                default_build_options: None,
-            }),
-            gleam: Some(LanguageConfig {
-                default: None,
-                installs: toml::Table::new(),
-                default_build_options: None,
-            }),
-            elixir: Some(LanguageConfig {
-                default: None,
-                installs: toml::Table::new(),
-                default_build_options: None,
-            }),
-            elp: Some(ComponentConfig {
-                default: None,
-                installs: toml::Table::new(),
-                default_build_options: None,
-            }),
-            rebar3: Some(ComponentConfig {
-                default: None,
-                installs: toml::Table::new(),
-                default_build_options: None,
-            }),
+            cache_size_limit: None,
+            github_token: None,
+            erlang: Some(LanguageConfig::default()),
+            gleam: Some(LanguageConfig::default()),
+            elixir: Some(LanguageConfig::default()),
+            lfe: Some(LanguageConfig::default()),
+            elp: Some(ComponentConfig::default()),
+            rebar3: Some(ComponentConfig::default()),
         };
 
         write_config(default_config.to_str().unwrap().to_string(), config)?;
@@ -530,6 +891,14 @@ pub fn home_config_file() -> Result<String> {
     Ok(default_config.to_str().unwrap().to_string())
 }
 
+// id set for a language or component in the `.beamup.toml` of the current directory
+pub fn local_id(name: String) -> Option<String> {
+    match get_local_id(name, &local_config()) {
+        Some(toml::Value::String(id)) => Some(id.to_owned()),
+        _ => None,
+    }
+}
+
 fn local_config() -> Option<toml::Table> {
     match fs::read_to_string(LOCAL_CONFIG_FILE) {
         Ok(local_config_str) => toml::from_str(local_config_str.as_str()).ok(),
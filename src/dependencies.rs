@@ -0,0 +1,244 @@
+use std::env;
+use std::env::split_paths;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A tool or library a build from source needs on the system
+#[derive(Debug, Clone, PartialEq)]
+pub enum Dependency {
+    CCompiler,
+    Make,
+    Perl,
+    Ncurses,
+    /// the OpenSSL headers, looked for in the given dir first if there is one
+    OpenSsl(Option<PathBuf>),
+    Cargo,
+}
+
+/// The families of distros that share a package manager and package names
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distro {
+    Debian,
+    Fedora,
+    Suse,
+    Arch,
+    Alpine,
+    MacOs,
+    Unknown,
+}
+
+impl Dependency {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dependency::CCompiler => "C compiler (cc, gcc or clang)",
+            Dependency::Make => "make",
+            Dependency::Perl => "perl",
+            Dependency::Ncurses => "ncurses headers",
+            Dependency::OpenSsl(_) => "OpenSSL headers",
+            Dependency::Cargo => "cargo",
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        match self {
+            Dependency::CCompiler => {
+                env::var("CC").is_ok_and(|cc| find_executable(&cc).is_some())
+                    || ["cc", "gcc", "clang"]
+                        .iter()
+                        .any(|cc| find_executable(cc).is_some())
+            }
+            Dependency::Make => find_executable("make").is_some(),
+            Dependency::Perl => find_executable("perl").is_some(),
+            Dependency::Ncurses => {
+                has_header(&[], "ncurses.h")
+                    || has_header(&[], "curses.h")
+                    || has_header(&[], "ncurses/ncurses.h")
+            }
+            Dependency::OpenSsl(dir) => {
+                let dirs: Vec<PathBuf> = dir.iter().map(|dir| dir.join("include")).collect();
+                has_header(&dirs, "openssl/ssl.h")
+            }
+            Dependency::Cargo => find_executable("cargo").is_some(),
+        }
+    }
+
+    /// The package providing the dependency on the distro, if known
+    pub fn package(&self, distro: Distro) -> Option<&'static str> {
+        match (self, distro) {
+            (_, Distro::Unknown) => None,
+            (Dependency::Cargo, Distro::MacOs) => Some("rust"),
+            (Dependency::Cargo, _) => Some("cargo"),
+            (Dependency::CCompiler | Dependency::Make, Distro::Debian) => Some("build-essential"),
+            (Dependency::CCompiler, Distro::Fedora | Distro::Suse) => Some("gcc"),
+            (Dependency::Make, Distro::Fedora | Distro::Suse) => Some("make"),
+            (Dependency::CCompiler | Dependency::Make, Distro::Arch) => Some("base-devel"),
+            (Dependency::CCompiler | Dependency::Make, Distro::Alpine) => Some("build-base"),
+            // comes with the Xcode command line tools
+            (Dependency::CCompiler | Dependency::Make | Dependency::Perl, Distro::MacOs) => None,
+            (Dependency::Perl, _) => Some("perl"),
+            (Dependency::Ncurses, Distro::Debian) => Some("libncurses-dev"),
+            (Dependency::Ncurses, Distro::Fedora | Distro::Suse) => Some("ncurses-devel"),
+            (Dependency::Ncurses, Distro::Arch | Distro::MacOs) => Some("ncurses"),
+            (Dependency::Ncurses, Distro::Alpine) => Some("ncurses-dev"),
+            (Dependency::OpenSsl(_), Distro::Debian) => Some("libssl-dev"),
+            (Dependency::OpenSsl(_), Distro::Fedora) => Some("openssl-devel"),
+            (Dependency::OpenSsl(_), Distro::Suse) => Some("libopenssl-devel"),
+            (Dependency::OpenSsl(_), Distro::Arch | Distro::MacOs) => Some("openssl"),
+            (Dependency::OpenSsl(_), Distro::Alpine) => Some("openssl-dev"),
+        }
+    }
+}
+
+impl Distro {
+    pub fn detect() -> Distro {
+        if env::consts::OS == "macos" {
+            return Distro::MacOs;
+        }
+
+        Distro::from_os_release(&fs::read_to_string("/etc/os-release").unwrap_or_default())
+    }
+
+    // the `ID` and `ID_LIKE` fields of os-release name the distro and the
+    // ones it is derived from, like `ID=ubuntu` and `ID_LIKE=debian`
+    fn from_os_release(os_release: &str) -> Distro {
+        let ids: Vec<&str> = os_release
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("ID=")
+                    .or_else(|| line.strip_prefix("ID_LIKE="))
+            })
+            .flat_map(|ids| ids.trim_matches('"').split_whitespace())
+            .collect();
+
+        for id in ids {
+            match id {
+                "debian" | "ubuntu" => return Distro::Debian,
+                "fedora" | "rhel" | "centos" => return Distro::Fedora,
+                "suse" | "opensuse" => return Distro::Suse,
+                "arch" => return Distro::Arch,
+                "alpine" => return Distro::Alpine,
+                _ => (),
+            }
+        }
+
+        Distro::Unknown
+    }
+
+    /// The command to install the packages with
+    pub fn install_command(&self, packages: &[&str]) -> Option<String> {
+        let command = match self {
+            Distro::Debian => "sudo apt-get install",
+            Distro::Fedora => "sudo dnf install",
+            Distro::Suse => "sudo zypper install",
+            Distro::Arch => "sudo pacman -S",
+            Distro::Alpine => "sudo apk add",
+            Distro::MacOs => "brew install",
+            Distro::Unknown => return None,
+        };
+
+        Some(format!("{command} {}", packages.join(" ")))
+    }
+}
+
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|file| file.is_file())
+}
+
+// looks for the header in the given dirs, then the dirs of `CPATH` and
+// `C_INCLUDE_PATH` and the usual system and Homebrew include dirs
+fn has_header(dirs: &[PathBuf], header: &str) -> bool {
+    let mut include_dirs = dirs.to_vec();
+    for key in ["CPATH", "C_INCLUDE_PATH"] {
+        if let Some(paths) = env::var_os(key) {
+            include_dirs.extend(split_paths(&paths));
+        }
+    }
+    include_dirs.extend(
+        [
+            "/usr/include",
+            "/usr/local/include",
+            "/opt/homebrew/include",
+            "/opt/homebrew/opt/ncurses/include",
+            "/opt/homebrew/opt/openssl/include",
+            "/usr/local/opt/ncurses/include",
+            "/usr/local/opt/openssl/include",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+    // with only the command line tools installed, macOS has its headers in the SDK
+    include_dirs.extend(sdk_include_dir());
+    // multiarch distros like Debian put some headers under the target triple
+    if let Ok(entries) = fs::read_dir("/usr/include") {
+        include_dirs.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir() && is_target_triple(path)),
+        );
+    }
+
+    include_dirs.iter().any(|dir| dir.join(header).is_file())
+}
+
+// the SDK given by `SDKROOT` or the one `xcrun` finds for the installed tools
+fn sdk_include_dir() -> Option<PathBuf> {
+    if env::consts::OS != "macos" {
+        return None;
+    }
+
+    let sdk = match env::var_os("SDKROOT") {
+        Some(sdk) => PathBuf::from(sdk),
+        None => {
+            let output = Command::new("xcrun")
+                .arg("--show-sdk-path")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+        }
+    };
+
+    Some(sdk.join("usr").join("include"))
+}
+
+fn is_target_triple(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with("-linux-gnu") || name.ends_with("-linux-musl"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_distros_from_os_release() {
+        let ubuntu = "NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\nVERSION_ID=\"24.04\"\n";
+        assert_eq!(Distro::from_os_release(ubuntu), Distro::Debian);
+
+        let rocky = "NAME=\"Rocky Linux\"\nID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n";
+        assert_eq!(Distro::from_os_release(rocky), Distro::Fedora);
+
+        let tumbleweed = "ID=\"opensuse-tumbleweed\"\nID_LIKE=\"opensuse suse\"\n";
+        assert_eq!(Distro::from_os_release(tumbleweed), Distro::Suse);
+
+        assert_eq!(Distro::from_os_release("ID=arch\n"), Distro::Arch);
+        assert_eq!(Distro::from_os_release("ID=alpine\n"), Distro::Alpine);
+        assert_eq!(Distro::from_os_release("ID=nixos\n"), Distro::Unknown);
+        assert_eq!(Distro::from_os_release(""), Distro::Unknown);
+    }
+
+    #[test]
+    fn install_command_for_distro() {
+        assert_eq!(
+            Distro::Debian.install_command(&["libssl-dev", "libncurses-dev"]),
+            Some("sudo apt-get install libssl-dev libncurses-dev".to_string())
+        );
+        assert_eq!(Distro::Unknown.install_command(&["perl"]), None);
+    }
+}
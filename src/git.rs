@@ -1,8 +1,16 @@
-use crate::github::GithubRelease;
+use crate::config;
+use crate::github;
+use crate::github::{GithubRelease, GithubRepo};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 // use http::Uri;
 
 pub enum GitRef {
     Branch(String),
+    Commit(String),
     Release(GithubRelease),
 }
 
@@ -10,6 +18,7 @@ impl std::fmt::Display for GitRef {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             GitRef::Branch(b) => write!(f, "{}", b),
+            GitRef::Commit(c) => write!(f, "{}", c),
             GitRef::Release(r) => write!(f, "{}", r),
         }
     }
@@ -18,3 +27,65 @@ impl std::fmt::Display for GitRef {
 // pub struct GitInfo {
 //     repo: Uri,
 // }
+
+/// Commit checked out in a local git repo, if it is one and git is available
+pub fn head_commit(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Tags and branches of a repo, cached locally by `fetch`
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Refs {
+    pub tags: Vec<String>,
+    pub branches: Vec<String>,
+}
+
+/// Fetches the refs of the repo from Github and updates the local cache
+pub fn fetch_refs(github_repo: &GithubRepo, config: &config::Config) -> Result<Refs> {
+    let refs = Refs {
+        tags: github::list_tags(github_repo, config)?,
+        branches: github::list_branches(github_repo, config)?,
+    };
+
+    let file = refs_cache_file(github_repo)?;
+    debug!("writing refs cache {file:?}");
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let toml_string = toml::to_string(&refs)?;
+    fs::write(&file, toml_string)
+        .wrap_err_with(|| format!("Failed to write refs cache {file:?}"))?;
+
+    Ok(refs)
+}
+
+/// Refs from the local cache, fetching them only if the repo was never fetched
+pub fn refs(github_repo: &GithubRepo, config: &config::Config) -> Result<Refs> {
+    let file = refs_cache_file(github_repo)?;
+    match fs::read_to_string(&file) {
+        Ok(toml_str) => {
+            debug!("using refs cache {file:?}");
+            toml::from_str(toml_str.as_str())
+                .wrap_err_with(|| format!("Failed to parse refs cache {file:?}"))
+        }
+        Err(_) => fetch_refs(github_repo, config),
+    }
+}
+
+fn refs_cache_file(GithubRepo { org, repo }: &GithubRepo) -> Result<PathBuf> {
+    Ok(config::data_dir()?
+        .join("beamup")
+        .join("refs")
+        .join(org)
+        .join(format!("{repo}.toml")))
+}
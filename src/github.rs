@@ -1,11 +1,19 @@
+use crate::cache;
+use crate::config;
+use crate::interrupt;
 use color_eyre::{eyre::eyre, eyre::Report, eyre::Result, eyre::WrapErr};
 use console::{style, Emoji};
 use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use octocrab::Octocrab;
+use std::env;
 use std::fs::File;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // http://unicode.org/emoji/charts/full-emoji-list.html
 static CHECKMARK: Emoji = Emoji("✅", "✅ ");
@@ -14,16 +22,36 @@ static CHECKMARK: Emoji = Emoji("✅", "✅ ");
 
 pub type GithubRelease = String;
 
+#[derive(Debug, Clone)]
 pub struct GithubRepo {
     pub org: String,
     pub repo: String,
 }
 
-pub fn print_releases(GithubRepo { org, repo }: &GithubRepo) {
+impl GithubRepo {
+    // accepts `https://github.com/<org>/<repo>`, `git@github.com:<org>/<repo>.git`
+    // or just `<org>/<repo>`
+    pub fn from_url(url: &str) -> Result<GithubRepo> {
+        let path = url.trim_end_matches('/').trim_end_matches(".git");
+        let mut parts = path.rsplit(['/', ':']);
+        match (parts.next(), parts.next()) {
+            (Some(repo), Some(org)) if !repo.is_empty() && !org.is_empty() => Ok(GithubRepo {
+                org: org.to_string(),
+                repo: repo.to_string(),
+            }),
+            _ => Err(eyre!("Unable to find a Github org and repo in {url}")),
+        }
+    }
+}
+
+pub fn print_releases(
+    GithubRepo { org, repo }: &GithubRepo,
+    config: &config::Config,
+) -> Result<()> {
     let rt = setup_tokio();
 
     let releases = rt.block_on(async {
-        let octocrab = octocrab::instance();
+        let octocrab = client(config)?;
         octocrab.repos(org, repo).releases().list().send().await
     });
 
@@ -33,9 +61,68 @@ pub fn print_releases(GithubRepo { org, repo }: &GithubRepo) {
                 let octocrab::models::repos::Release { tag_name, .. } = release;
                 println!("{tag_name}");
             }
+            Ok(())
+        }
+        Err(err) => {
+            debug!("{err:?}");
+            Err(api_error(&rt, err, config))
+                .wrap_err(format!("Failed to fetch releases for {org}/{repo}"))
+        }
+    }
+}
+
+pub fn list_tags(
+    GithubRepo { org, repo }: &GithubRepo,
+    config: &config::Config,
+) -> Result<Vec<String>> {
+    let rt = setup_tokio();
+
+    debug!("Listing tags of {}/{}", org, repo);
+    let tags = rt.block_on(async {
+        let octocrab = client(config)?;
+        let page = octocrab
+            .repos(org, repo)
+            .list_tags()
+            .per_page(100)
+            .send()
+            .await?;
+        octocrab.all_pages(page).await
+    });
+
+    match tags {
+        Ok(tags) => Ok(tags.into_iter().map(|tag| tag.name).collect()),
+        Err(err) => {
+            debug!("{err:?}");
+            Err(api_error(&rt, err, config))
+                .wrap_err(format!("Failed fetching tags from {org:}/{repo:}"))
         }
-        _ => {
-            error!("Failed to fetch releases for ");
+    }
+}
+
+pub fn list_branches(
+    GithubRepo { org, repo }: &GithubRepo,
+    config: &config::Config,
+) -> Result<Vec<String>> {
+    let rt = setup_tokio();
+
+    debug!("Listing branches of {}/{}", org, repo);
+    let branches = rt.block_on(async {
+        let octocrab = client(config)?;
+        let page = octocrab
+            .repos(org, repo)
+            .list_branches()
+            .per_page(100)
+            .send()
+            .await?;
+        octocrab.all_pages(page).await
+    });
+
+    match branches {
+        Ok(branches) => Ok(branches.into_iter().map(|branch| branch.name).collect()),
+        Err(err) => {
+            debug!("{err:?}");
+            Err(api_error(&rt, err, config))
+                .wrap_err(format!("Failed fetching branches from {org:}/{repo:}"))
         }
     }
 }
@@ -44,13 +131,14 @@ pub fn download_release_tarball(
     out_dir: &Path,
     GithubRepo { org, repo }: &GithubRepo,
     tag: &String,
-) -> Result<PathBuf, Report> {
+    config: &config::Config,
+) -> Result<(PathBuf, String), Report> {
     let rt = setup_tokio();
 
     let release_result = if tag == "latest" {
         debug!("Getting latest release from {}/{}", org, repo);
         rt.block_on(async {
-            octocrab::instance()
+            client(config)?
                 .repos(org, repo)
                 .releases()
                 .get_latest()
@@ -59,7 +147,7 @@ pub fn download_release_tarball(
     } else {
         debug!("Getting {} release from {}/{}", tag, org, repo);
         rt.block_on(async {
-            octocrab::instance()
+            client(config)?
                 .repos(org, repo)
                 .releases()
                 .get_by_tag(tag)
@@ -80,15 +168,13 @@ pub fn download_release_tarball(
         }
         Err(err) => {
             debug!("{err:?}");
-            return Err(err).wrap_err(format!(
+            return Err(api_error(&rt, err, config)).wrap_err(format!(
                 "Failed downloading release tarball Github release {tag:} from {org:}/{repo:}"
             ));
         }
     };
 
     let file = out_dir.join(repo.to_owned() + ".tar.gz");
-    let dest = std::fs::File::create(&file)
-        .wrap_err_with(|| format!("Failed to create asset download file {:?}", file))?;
 
     debug!(
         "Downloading release source tarball {:?} to {:?}",
@@ -96,13 +182,63 @@ pub fn download_release_tarball(
         file
     );
 
-    http_download(
-        dest,
+    let sha256 = cached_download(
+        &file,
         url.as_str(),
+        None,
         format!("Downloading release source tarball from {org}/{repo}"),
+        config,
     )?;
 
-    Ok(file)
+    Ok((file, sha256))
+}
+
+// full SHA of the commit a branch, tag or (possibly short) commit SHA points to
+pub fn resolve_commit(
+    GithubRepo { org, repo }: &GithubRepo,
+    git_ref: &str,
+    config: &config::Config,
+) -> Result<String> {
+    let rt = setup_tokio();
+
+    debug!("Resolving commit of {} in {}/{}", git_ref, org, repo);
+    let commit = rt.block_on(async { client(config)?.commits(org, repo).get(git_ref).await });
+
+    match commit {
+        Ok(commit) => Ok(commit.sha),
+        Err(err) => {
+            debug!("{err:?}");
+            Err(api_error(&rt, err, config)).wrap_err(format!(
+                "Failed resolving commit of {git_ref:} in {org:}/{repo:}"
+            ))
+        }
+    }
+}
+
+// any branch, tag or commit can be downloaded as a tarball from codeload, unlike
+// `download_release_tarball` which only works for Github releases
+pub fn download_ref_tarball(
+    out_dir: &Path,
+    GithubRepo { org, repo }: &GithubRepo,
+    git_ref: &String,
+    config: &config::Config,
+) -> Result<(PathBuf, String), Report> {
+    let url = format!("https://codeload.github.com/{org}/{repo}/tar.gz/{git_ref}");
+
+    let file = out_dir.join(repo.to_owned() + ".tar.gz");
+
+    debug!("Downloading source tarball {:?} to {:?}", url, file);
+
+    let sha256 = cached_download(
+        &file,
+        url.as_str(),
+        None,
+        format!("Downloading {git_ref} source tarball from {org}/{repo}"),
+        config,
+    )
+    .wrap_err_with(|| format!("Failed downloading {git_ref} source tarball from {org}/{repo}"))?;
+
+    Ok((file, sha256))
 }
 
 pub fn download_asset(
@@ -110,13 +246,14 @@ pub fn download_asset(
     out_dir: &Path,
     GithubRepo { org, repo }: &GithubRepo,
     tag: &str,
-) -> Result<PathBuf, Report> {
+    config: &config::Config,
+) -> Result<(PathBuf, String), Report> {
     let rt = setup_tokio();
 
     let release_result = if tag == "latest" {
         debug!("Getting latest release from {}/{}", org, repo);
         rt.block_on(async {
-            octocrab::instance()
+            client(config)?
                 .repos(org, repo)
                 .releases()
                 .get_latest()
@@ -125,7 +262,7 @@ pub fn download_asset(
     } else {
         debug!("Getting {} release from {}/{}", tag, org, repo);
         rt.block_on(async {
-            octocrab::instance()
+            client(config)?
                 .repos(org, repo)
                 .releases()
                 .get_by_tag(tag)
@@ -137,34 +274,37 @@ pub fn download_asset(
         Ok(octocrab::models::repos::Release { assets, .. }) => assets,
         Err(err) => {
             debug!("{err:?}");
-            return Err(err).wrap_err(format!(
+            return Err(api_error(&rt, err, config)).wrap_err(format!(
                 "Failed fetching Github release {tag:} from {org:}/{repo:}"
             ));
         }
     };
 
     debug!("looking for asset {asset_prefix}");
+    // the asset regexes also match the checksum files published next to assets
     match assets
         .iter()
+        .filter(|&asset| !is_checksum_file(&asset.name))
         .find(|&asset| asset_prefix.is_match(&asset.name))
     {
         Some(asset) => {
             let file = out_dir.join(&asset.name);
-            let dest = std::fs::File::create(&file)
-                .wrap_err_with(|| format!("Failed to create asset download file {:?}", file))?;
 
             debug!(
                 "Downloading release asset {:?} to {:?}",
                 &asset.browser_download_url, file
             );
 
-            http_download(
-                dest,
+            let expected_sha256 = published_sha256(asset, &assets, config)?;
+            let sha256 = cached_download(
+                &file,
                 asset.browser_download_url.as_str(),
+                expected_sha256.as_deref(),
                 format!("Downloading release source tarball from {org}/{repo}"),
+                config,
             )?;
 
-            Ok(file)
+            Ok((file, sha256))
         }
         None => {
             let e: Report =
@@ -175,9 +315,161 @@ pub fn download_asset(
     }
 }
 
-fn http_download(mut dest: File, url: &str, progress_msg: String) -> Result<()> {
+// the sha256 published for the asset, either in the `digest` Github computes
+// for assets or in a `<asset>.sha256` file or checksums file on the release
+fn published_sha256(
+    asset: &octocrab::models::repos::Asset,
+    assets: &[octocrab::models::repos::Asset],
+    config: &config::Config,
+) -> Result<Option<String>> {
+    if let Some(sha256) = asset
+        .digest
+        .as_ref()
+        .and_then(|digest| digest.strip_prefix("sha256:"))
+    {
+        debug!("using digest of asset {} for its sha256", asset.name);
+        return Ok(Some(sha256.to_lowercase()));
+    }
+
+    let checksum_files = [
+        format!("{}.sha256", asset.name),
+        format!("{}.sha256sum", asset.name),
+        format!("{}.sha256.txt", asset.name),
+        "SHA256SUMS".to_string(),
+        "SHA256SUMS.txt".to_string(),
+        "sha256sums.txt".to_string(),
+        "checksums.txt".to_string(),
+    ];
+    let Some(checksum_asset) = checksum_files
+        .iter()
+        .find_map(|name| assets.iter().find(|a| &a.name == name))
+    else {
+        debug!("no published sha256 found for asset {}", asset.name);
+        return Ok(None);
+    };
+
+    debug!(
+        "downloading sha256 of asset {} from {}",
+        asset.name, checksum_asset.browser_download_url
+    );
+    let checksums = http_get(checksum_asset.browser_download_url.as_str(), config)?
+        .into_string()
+        .wrap_err_with(|| format!("Failed to read checksum file {}", checksum_asset.name))?;
+
+    match parse_sha256(&checksums, &asset.name) {
+        Some(sha256) => Ok(Some(sha256)),
+        None => Err(eyre!(
+            "No sha256 for {} found in checksum file {}",
+            asset.name,
+            checksum_asset.name
+        )),
+    }
+}
+
+pub fn is_checksum_file(name: &str) -> bool {
+    [".sha256", ".sha256sum", ".sha256.txt"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+// checksum files have the `sha256sum` format of `<sha256>  <file>` lines, or
+// are just the sha256 when they are for a single file
+fn parse_sha256(checksums: &str, file_name: &str) -> Option<String> {
+    let is_sha256 = |s: &str| s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit());
+
+    let mut lines = checksums.lines().map(|line| line.split_whitespace());
+    lines
+        .find_map(|mut fields| match (fields.next(), fields.next()) {
+            (Some(sha256), None) if is_sha256(sha256) => Some(sha256),
+            // `*` marks a file hashed in binary mode
+            (Some(sha256), Some(name))
+                if is_sha256(sha256)
+                    && Path::new(name.trim_start_matches('*')).file_name()
+                        == Some(file_name.as_ref()) =>
+            {
+                Some(sha256)
+            }
+            _ => None,
+        })
+        .map(|sha256| sha256.to_lowercase())
+}
+
+// downloads go through the cache, so a URL is only downloaded once. A failure
+// to use the cache only means downloading again and isn't an error. Returns
+// the sha256 of the download, which must be the expected one if there is one.
+pub fn cached_download(
+    file: &Path,
+    url: &str,
+    expected_sha256: Option<&str>,
+    progress_msg: String,
+    config: &config::Config,
+) -> Result<String> {
+    if let Some(blob) = cache::lookup(url) {
+        info!("Using cached download of {url}");
+        std::fs::copy(&blob, file)
+            .wrap_err_with(|| format!("Failed to copy cached download {blob:?} to {file:?}"))?;
+        let sha256 = cache::sha256_file(file)?;
+        match expected_sha256 {
+            Some(expected) if expected != sha256 => {
+                warn!("Cached download of {url} doesn't match its published sha256, downloading it again");
+                cache::remove(url);
+            }
+            _ => return Ok(sha256),
+        }
+    }
+
+    let sha256 = download(file, url, expected_sha256, progress_msg, config)?;
+
+    // only added to the cache once verified
+    if let Err(e) = cache::insert(url, file, &sha256, config) {
+        warn!("Unable to add download of {url} to the cache: {e}");
+    }
+
+    Ok(sha256)
+}
+
+/// Downloads the URL to the file without going through the cache, returning
+/// its verified sha256
+pub fn download(
+    file: &Path,
+    url: &str,
+    expected_sha256: Option<&str>,
+    progress_msg: String,
+    config: &config::Config,
+) -> Result<String> {
+    let dest = std::fs::File::create(file)
+        .wrap_err_with(|| format!("Failed to create asset download file {:?}", file))?;
+    http_download(dest, url, progress_msg, config)?;
+
+    verify_sha256(file, url, expected_sha256)
+}
+
+/// The sha256 of the file downloaded from the URL, which is removed if it
+/// isn't the expected one
+pub fn verify_sha256(file: &Path, url: &str, expected_sha256: Option<&str>) -> Result<String> {
+    let sha256 = cache::sha256_file(file)?;
+    match expected_sha256 {
+        Some(expected) if expected != sha256 => {
+            let _ = std::fs::remove_file(file);
+            return Err(eyre!(
+                "Checksum mismatch for {url}: expected sha256 {expected} but downloaded {sha256}"
+            ));
+        }
+        Some(_) => info!("Verified sha256 of {url}"),
+        None => debug!("no sha256 to verify {url} with"),
+    }
+
+    Ok(sha256)
+}
+
+fn http_download(
+    mut dest: File,
+    url: &str,
+    progress_msg: String,
+    config: &config::Config,
+) -> Result<()> {
     let started = Instant::now();
-    let response = ureq::get(url).call()?;
+    let response = http_get(url, config)?;
 
     if let Some(length) = response
         .header("content-length")
@@ -197,7 +489,7 @@ fn http_download(mut dest: File, url: &str, progress_msg: String) -> Result<()>
                 .template("{prefix:>12.bright.cyan} [{bar:27}] {bytes:>9}/{total_bytes:9}  {bytes_per_sec} {elapsed:>4}/{eta:4} - {msg:.cyan}")?.progress_chars("=> "));
         bar.set_length(length);
 
-        std::io::copy(&mut bar.wrap_read(response.into_reader()), &mut dest)?;
+        copy_download(bar.wrap_read(response.into_reader()), &mut dest)?;
 
         bar.finish_and_clear();
     } else {
@@ -212,7 +504,7 @@ fn http_download(mut dest: File, url: &str, progress_msg: String) -> Result<()>
 
         pb.set_message(progress_msg.clone());
 
-        std::io::copy(&mut response.into_reader(), &mut dest)?;
+        copy_download(response.into_reader(), &mut dest)?;
 
         pb.println(format!(" {} {}", CHECKMARK, progress_msg));
 
@@ -228,6 +520,117 @@ fn http_download(mut dest: File, url: &str, progress_msg: String) -> Result<()>
     Ok(())
 }
 
+// copies in chunks so an interrupted build stops downloading right away
+fn copy_download(mut reader: impl Read, dest: &mut File) -> Result<()> {
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        interrupt::check()?;
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        dest.write_all(&buffer[..n])?;
+    }
+}
+
+// a token raises the Github API rate limit from 60 to 5000 requests an hour
+fn github_token(config: &config::Config) -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .find_map(|key| env::var(key).ok().filter(|token| !token.is_empty()))
+        .or_else(|| config::lookup_github_token(config))
+}
+
+// must be called from within the tokio runtime
+fn client(config: &config::Config) -> octocrab::Result<Arc<Octocrab>> {
+    match github_token(config) {
+        Some(token) => {
+            debug!("using a Github token for API requests");
+            Ok(Arc::new(Octocrab::builder().personal_token(token).build()?))
+        }
+        None => Ok(octocrab::instance()),
+    }
+}
+
+// a rate limited request only says the limit was exceeded, so the rate limit,
+// which doesn't count against itself, is asked for when the limit resets
+fn api_error(
+    rt: &tokio::runtime::Runtime,
+    err: octocrab::Error,
+    config: &config::Config,
+) -> Report {
+    match &err {
+        octocrab::Error::GitHub { source, .. }
+            if (source.status_code == 403 || source.status_code == 429)
+                && source.message.contains("rate limit") =>
+        {
+            let reset = rt
+                .block_on(async { client(config)?.ratelimit().get().await })
+                .ok()
+                .map(|rate_limit| rate_limit.resources.core.reset);
+            rate_limit_error(reset, config)
+        }
+        _ => Report::new(err),
+    }
+}
+
+// downloads from Github count against the same rate limit as the API, so they
+// are authenticated too. Any other host never sees the token.
+pub fn http_get(url: &str, config: &config::Config) -> Result<ureq::Response> {
+    let mut request = ureq::get(url);
+    if is_github_url(url) {
+        if let Some(token) = github_token(config) {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+    }
+
+    match request.call() {
+        Ok(response) => Ok(response),
+        Err(ureq::Error::Status(403 | 429, response))
+            if response.header("x-ratelimit-remaining") == Some("0") =>
+        {
+            let reset = response
+                .header("x-ratelimit-reset")
+                .and_then(|reset| reset.parse().ok());
+            Err(rate_limit_error(reset, config)).wrap_err(format!("Failed downloading {url}"))
+        }
+        Err(err) => Err(err).wrap_err(format!("Failed downloading {url}")),
+    }
+}
+
+fn is_github_url(url: &str) -> bool {
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    host == "github.com" || host.ends_with(".github.com")
+}
+
+// `reset` is when the rate limit resets in seconds since the epoch
+fn rate_limit_error(reset: Option<u64>, config: &config::Config) -> Report {
+    let resets = match reset {
+        Some(reset) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            format!(
+                ", it resets in {}",
+                HumanDuration(Duration::from_secs(reset.saturating_sub(now)))
+            )
+        }
+        None => String::new(),
+    };
+    let hint = match github_token(config) {
+        Some(_) => "",
+        None => ". Set GITHUB_TOKEN, GH_TOKEN or `github_token` in the config for a higher limit",
+    };
+
+    eyre!("Github API rate limit exceeded{resets}{hint}")
+}
+
 // just need this for ocotocrab
 fn setup_tokio() -> tokio::runtime::Runtime {
     tokio::runtime::Builder::new_current_thread()
@@ -235,3 +638,104 @@ fn setup_tokio() -> tokio::runtime::Runtime {
         .build()
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ERLANG_SHA256: &str = "cdb8cde8ab8d9e03a81b38304d8f83ce31ce834fc5eee9c695ebbd3384f59b17";
+    static GLEAM_SHA256: &str = "5c1d4d0b0cfbed6dd3c1a52ee4bc2a2d5d45b6a0ad1e9b8d6ef0b0c3cf7a6a41";
+
+    #[test]
+    fn parses_single_sha256() {
+        assert_eq!(
+            parse_sha256(&format!("{ERLANG_SHA256}\n"), "erlang.tar.gz").as_deref(),
+            Some(ERLANG_SHA256)
+        );
+        assert_eq!(
+            parse_sha256(&ERLANG_SHA256.to_uppercase(), "erlang.tar.gz").as_deref(),
+            Some(ERLANG_SHA256)
+        );
+        assert_eq!(parse_sha256("not a sha256", "erlang.tar.gz"), None);
+        assert_eq!(parse_sha256(&ERLANG_SHA256[1..], "erlang.tar.gz"), None);
+    }
+
+    #[test]
+    fn parses_sha256sum_lines() {
+        let checksums = format!(
+            "{GLEAM_SHA256}  gleam-v1.5.0-x86_64-unknown-linux-musl.tar.gz\n\
+             {ERLANG_SHA256}  erlang-27.1-x64.tar.gz\n"
+        );
+        assert_eq!(
+            parse_sha256(&checksums, "erlang-27.1-x64.tar.gz").as_deref(),
+            Some(ERLANG_SHA256)
+        );
+        assert_eq!(
+            parse_sha256(&checksums, "gleam-v1.5.0-x86_64-unknown-linux-musl.tar.gz").as_deref(),
+            Some(GLEAM_SHA256)
+        );
+        assert_eq!(parse_sha256(&checksums, "erlang-27.1-arm64.tar.gz"), None);
+        // a name must match whole, not just end the same
+        assert_eq!(parse_sha256(&checksums, "x64.tar.gz"), None);
+    }
+
+    #[test]
+    fn parses_binary_marker_and_paths() {
+        let checksums =
+            format!("{GLEAM_SHA256} *gleam.zip\n{ERLANG_SHA256} *dist/erlang-27.1-x64.tar.gz\n");
+        assert_eq!(
+            parse_sha256(&checksums, "gleam.zip").as_deref(),
+            Some(GLEAM_SHA256)
+        );
+        assert_eq!(
+            parse_sha256(&checksums, "erlang-27.1-x64.tar.gz").as_deref(),
+            Some(ERLANG_SHA256)
+        );
+    }
+
+    #[test]
+    fn finds_checksum_files() {
+        assert!(is_checksum_file("erlang-27.1-x64.tar.gz.sha256"));
+        assert!(is_checksum_file("erlang-27.1-x64.tar.gz.sha256sum"));
+        assert!(is_checksum_file("erlang-27.1-x64.tar.gz.sha256.txt"));
+        assert!(!is_checksum_file("erlang-27.1-x64.tar.gz"));
+    }
+
+    fn asset(name: &str, digest: Option<&str>) -> octocrab::models::repos::Asset {
+        serde_json::from_value(serde_json::json!({
+            "url": format!("https://api.github.com/assets/{name}"),
+            "browser_download_url": format!("https://github.com/download/{name}"),
+            "id": 1,
+            "node_id": "1",
+            "name": name,
+            "label": null,
+            "state": "uploaded",
+            "content_type": "application/gzip",
+            "size": 6,
+            "digest": digest,
+            "download_count": 0,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "uploader": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn uses_published_digest() {
+        let config: config::Config = toml::from_str("install_dir = \"\"").unwrap();
+        let digest = format!("sha256:{}", ERLANG_SHA256.to_uppercase());
+        let erlang = asset("erlang-27.1-x64.tar.gz", Some(&digest));
+        let gleam = asset("gleam.tar.gz", None);
+        let assets = [erlang.clone(), gleam.clone()];
+
+        assert_eq!(
+            published_sha256(&erlang, &assets, &config)
+                .unwrap()
+                .as_deref(),
+            Some(ERLANG_SHA256)
+        );
+        // without a digest or checksum file there is nothing to verify with
+        assert_eq!(published_sha256(&gleam, &assets, &config).unwrap(), None);
+    }
+}
@@ -0,0 +1,69 @@
+use color_eyre::eyre::{eyre, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// set by the handler installed for the length of a build, and checked between
+// and during its downloads, copies and steps so the build can be rolled back
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Fails if Ctrl-C or SIGTERM was received since the handler was set
+pub fn check() -> Result<()> {
+    match interrupted() {
+        true => Err(eyre!("Interrupted")),
+        false => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn interrupt_handler(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(unix)]
+pub fn set_handler() {
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    unsafe {
+        libc::signal(libc::SIGINT, interrupt_handler as libc::sighandler_t);
+        libc::signal(libc::SIGTERM, interrupt_handler as libc::sighandler_t);
+    }
+}
+
+// once the build is done Ctrl-C exits beamup again
+#[cfg(unix)]
+pub fn reset_handler() {
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+        libc::signal(libc::SIGTERM, libc::SIG_DFL);
+    }
+}
+
+// thanks rustup command.rs
+#[cfg(windows)]
+unsafe extern "system" fn ctrlc_handler(_: u32) -> windows_sys::Win32::Foundation::BOOL {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    windows_sys::Win32::Foundation::TRUE
+}
+
+#[cfg(windows)]
+pub fn set_handler() {
+    use windows_sys::Win32::Foundation::TRUE;
+    use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    unsafe {
+        SetConsoleCtrlHandler(Some(ctrlc_handler), TRUE);
+    }
+}
+
+#[cfg(windows)]
+pub fn reset_handler() {
+    use windows_sys::Win32::Foundation::FALSE;
+    use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+    unsafe {
+        SetConsoleCtrlHandler(Some(ctrlc_handler), FALSE);
+    }
+}
@@ -1,13 +1,18 @@
+use crate::cmd::build::{BuildSettings, BuildStep};
 use crate::config;
+use crate::dependencies::Dependency;
 use crate::github::GithubRepo;
 use crate::languages;
+use crate::mirror::Mirror;
 use clap::ValueEnum;
 use color_eyre::eyre::{eyre, Result};
+use std::path::Path;
 use std::path::PathBuf;
 use strum::IntoEnumIterator;
 pub mod elixir;
 pub mod erlang;
 pub mod gleam;
+pub mod lfe;
 
 #[derive(ValueEnum, Debug, Clone, PartialEq, EnumIter)]
 pub enum Libc {
@@ -20,6 +25,7 @@ pub enum Language {
     Elixir,
     Erlang,
     Gleam,
+    Lfe,
 }
 
 pub fn print() {
@@ -34,6 +40,7 @@ impl std::fmt::Display for Language {
             Language::Erlang => write!(f, "erlang"),
             Language::Gleam => write!(f, "gleam"),
             Language::Elixir => write!(f, "elixir"),
+            Language::Lfe => write!(f, "lfe"),
         }
     }
 }
@@ -43,24 +50,40 @@ pub fn bins(_config: &config::Config) -> Vec<(String, Language)> {
     let mut elixir_bins = elixir::bins();
     let mut erlang_bins = erlang::bins();
     let mut gleam_bins = gleam::bins();
+    let mut lfe_bins = lfe::bins();
 
     bins.append(&mut elixir_bins);
     bins.append(&mut erlang_bins);
     bins.append(&mut gleam_bins);
+    bins.append(&mut lfe_bins);
 
     bins
 }
 
 pub trait Installable {
     fn default_build_options(&self, config: &config::Config) -> String;
+    fn build_profile(&self, name: &str, config: &config::Config) -> Result<config::BuildProfile>;
 
-    fn binary_repo(&self) -> GithubRepo;
-    fn source_repo(&self) -> GithubRepo;
+    fn binary_repo(&self, repo: &Option<String>, config: &config::Config) -> Result<GithubRepo>;
+    fn source_repo(&self, repo: &Option<String>, config: &config::Config) -> Result<GithubRepo>;
+    fn mirror(&self, repo: &Option<String>, config: &config::Config) -> Option<Mirror>;
 
-    fn release_dir(&self, id: &String) -> Result<PathBuf>;
-    fn extract_dir(&self, id: &String) -> Result<PathBuf>;
+    fn release_dir(&self, id: &str) -> Result<PathBuf>;
+    fn extract_dir(&self, id: &str) -> Result<PathBuf>;
+    fn build_log_file(&self, id: &str) -> Result<PathBuf>;
 
     fn asset_prefix(&self, libc: &Option<Libc>) -> Result<regex::Regex>;
+
+    // the pipeline run in the unpacked source to build and install into `install_dir`
+    fn build_steps(
+        &self,
+        install_dir: &Path,
+        settings: &BuildSettings,
+    ) -> Result<Vec<BuildStep<'static>>>;
+    // the tools and libraries the build needs, checked for before building
+    fn build_dependencies(&self, build_options: &str) -> Result<Vec<Dependency>>;
+    // extra environment variables the build steps are run with
+    fn build_env(&self) -> Result<Vec<(String, String)>>;
 }
 
 impl Installable for Language {
@@ -68,8 +91,16 @@ impl Installable for Language {
         config::lookup_default_build_options(self, config)
     }
 
-    fn binary_repo(&self) -> GithubRepo {
-        match self {
+    fn build_profile(&self, name: &str, config: &config::Config) -> Result<config::BuildProfile> {
+        config::lookup_build_profile(self, name, config)
+    }
+
+    fn binary_repo(&self, repo: &Option<String>, config: &config::Config) -> Result<GithubRepo> {
+        if let Some(id) = repo {
+            return config::lookup_repo(&config::Tool::Language(self.clone()), id, config);
+        }
+
+        Ok(match self {
             Language::Elixir => GithubRepo {
                 org: "elixir-lang".to_string(),
                 repo: "elixir".to_string(),
@@ -92,11 +123,23 @@ impl Installable for Language {
                 org: "gleam-lang".to_string(),
                 repo: "gleam".to_string(),
             },
-        }
+            Language::Lfe => GithubRepo {
+                org: "lfe".to_string(),
+                repo: "lfe".to_string(),
+            },
+        })
     }
 
-    fn source_repo(&self) -> GithubRepo {
-        match self {
+    fn mirror(&self, repo: &Option<String>, config: &config::Config) -> Option<Mirror> {
+        config::lookup_mirror(&config::Tool::Language(self.clone()), repo, config)
+    }
+
+    fn source_repo(&self, repo: &Option<String>, config: &config::Config) -> Result<GithubRepo> {
+        if let Some(id) = repo {
+            return config::lookup_repo(&config::Tool::Language(self.clone()), id, config);
+        }
+
+        Ok(match self {
             Language::Elixir => GithubRepo {
                 org: "elixir-lang".to_string(),
                 repo: "elixir".to_string(),
@@ -109,14 +152,22 @@ impl Installable for Language {
                 org: "gleam-lang".to_string(),
                 repo: "gleam".to_string(),
             },
-        }
+            Language::Lfe => GithubRepo {
+                org: "lfe".to_string(),
+                repo: "lfe".to_string(),
+            },
+        })
     }
 
-    fn release_dir(&self, id: &String) -> Result<PathBuf> {
+    fn release_dir(&self, id: &str) -> Result<PathBuf> {
         languages::release_dir(self.to_string(), id)
     }
 
-    fn extract_dir(&self, id: &String) -> Result<PathBuf> {
+    fn build_log_file(&self, id: &str) -> Result<PathBuf> {
+        languages::build_log_file(self.to_string(), id)
+    }
+
+    fn extract_dir(&self, id: &str) -> Result<PathBuf> {
         match self {
             Language::Gleam => {
                 let release_dir = languages::release_dir(self.to_string(), id)?;
@@ -131,6 +182,36 @@ impl Installable for Language {
             Language::Elixir => elixir::asset_prefix(),
             Language::Erlang => erlang::asset_prefix(libc),
             Language::Gleam => gleam::asset_prefix(),
+            Language::Lfe => lfe::asset_prefix(),
+        }
+    }
+
+    fn build_steps(
+        &self,
+        install_dir: &Path,
+        settings: &BuildSettings,
+    ) -> Result<Vec<BuildStep<'static>>> {
+        match self {
+            Language::Elixir => elixir::build_steps(install_dir, settings),
+            Language::Erlang => erlang::build_steps(install_dir, settings),
+            Language::Gleam => gleam::build_steps(install_dir, settings),
+            Language::Lfe => lfe::build_steps(install_dir, settings),
+        }
+    }
+
+    fn build_dependencies(&self, build_options: &str) -> Result<Vec<Dependency>> {
+        match self {
+            Language::Erlang => erlang::build_dependencies(build_options),
+            Language::Elixir | Language::Lfe => Ok(vec![Dependency::Make]),
+            // cargo needs a C compiler as the linker
+            Language::Gleam => Ok(vec![Dependency::Cargo, Dependency::CCompiler]),
+        }
+    }
+
+    fn build_env(&self) -> Result<Vec<(String, String)>> {
+        match self {
+            Language::Elixir | Language::Lfe => erlang::build_env(),
+            _ => Ok(vec![]),
         }
     }
 }
@@ -142,7 +223,7 @@ pub fn bin_to_language(bin: String, config: &config::Config) -> Result<languages
     }
 }
 
-pub fn release_dir(language_str: String, id: &String) -> Result<PathBuf> {
+pub fn release_dir(language_str: String, id: &str) -> Result<PathBuf> {
     let release_dir = config::data_dir()?
         .join("beamup")
         .join(language_str)
@@ -150,3 +231,13 @@ pub fn release_dir(language_str: String, id: &String) -> Result<PathBuf> {
 
     Ok(release_dir)
 }
+
+pub fn build_log_file(language_str: String, id: &str) -> Result<PathBuf> {
+    let log_file = config::data_dir()?
+        .join("beamup")
+        .join("logs")
+        .join(language_str)
+        .join(format!("{id}.log"));
+
+    Ok(log_file)
+}
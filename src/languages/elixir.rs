@@ -1,7 +1,10 @@
+use crate::cmd::build;
+use crate::cmd::build::{BuildSettings, BuildStep};
 use crate::config;
 use crate::languages::Language;
 use color_eyre::eyre::{eyre, Result, WrapErr};
 use regex::Regex;
+use std::path::Path;
 
 #[cfg(unix)]
 pub fn bins() -> Vec<(String, Language)> {
@@ -32,3 +35,12 @@ pub fn asset_prefix() -> Result<regex::Regex> {
         Err(_) => Err(eyre!("No Erlang install found.")),
     }
 }
+
+// Elixir has no configure step, any user build options are passed to `make`
+// as variables, like `ERLC_OPTS=...`
+pub fn build_steps(
+    install_dir: &Path,
+    settings: &BuildSettings,
+) -> Result<Vec<BuildStep<'static>>> {
+    build::make_steps(install_dir, settings)
+}
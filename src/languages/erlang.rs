@@ -1,6 +1,47 @@
+use crate::cmd::build;
+use crate::cmd::build::{BuildSettings, BuildStep, CheckResult};
+use crate::config;
+use crate::dependencies::Dependency;
 use crate::languages::{Language, Libc};
+use clap::ValueEnum;
 use color_eyre::eyre::{eyre, Result, WrapErr};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::env::{join_paths, split_paths};
+use std::path::{Path, PathBuf};
+
+/// Emulator types built in addition to the default `opt` one, selected with
+/// `make TYPE=<type>` and run with `erl -emu_type <type>`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmuType {
+    Debug,
+    Asan,
+    Valgrind,
+}
+
+impl EmuType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmuType::Debug => "debug",
+            EmuType::Asan => "asan",
+            EmuType::Valgrind => "valgrind",
+        }
+    }
+}
+
+/// Which docs to build and install with the release
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Docs {
+    None,
+    /// the doc chunks used by the shell's `h/1`
+    #[default]
+    Chunks,
+    /// chunks and the HTML docs, which need ex_doc
+    Html,
+}
 
 #[cfg(unix)]
 pub fn bins() -> Vec<(String, Language)> {
@@ -60,16 +101,236 @@ pub fn asset_prefix(libc: &Option<Libc>) -> Result<regex::Regex> {
         ("x86_64", "windows") => {
             Regex::new("otp_win64_.*.exe").wrap_err("Unable to create asset regex")
         }
-        ("x86_64", "macos") => {
-            Regex::new("otp-x86_64-apple-darwin.tar.gz").wrap_err("Unable to create asset regex")
-        }
-        ("aarch64", "macos") => {
-            Regex::new("otp-aarch64-apple-darwin.tar.gz").wrap_err("Unable to create asset regex")
-        }
-        ("aarch64", "linux") => Regex::new(format!("erlang-.*-arm64{libc}.tar.gz").as_str())
+        ("x86_64", "macos") => Regex::new("otp-x86_64-apple-darwin.tar.(gz|xz|zst)")
             .wrap_err("Unable to create asset regex"),
-        ("x86_64", "linux") => Regex::new(format!("erlang-.*-x64{libc}.tar.gz").as_str())
+        ("aarch64", "macos") => Regex::new("otp-aarch64-apple-darwin.tar.(gz|xz|zst)")
+            .wrap_err("Unable to create asset regex"),
+        ("aarch64", "linux") => {
+            Regex::new(format!("erlang-.*-arm64{libc}.tar.(gz|xz|zst)").as_str())
+                .wrap_err("Unable to create asset regex")
+        }
+        ("x86_64", "linux") => Regex::new(format!("erlang-.*-x64{libc}.tar.(gz|xz|zst)").as_str())
             .wrap_err("Unable to create asset regex"),
         _ => Err(eyre!("Unknown architecture or OS for installing Erlang")),
     }
 }
+
+pub fn build_steps(
+    install_dir: &Path,
+    settings: &BuildSettings,
+) -> Result<Vec<BuildStep<'static>>> {
+    let num_cpus = num_cpus::get().to_string();
+
+    // split the configure options into a vector of String in a shell sensitive way
+    // eg.
+    //  from:
+    //      user_build_options0: --without-wx --without-observer --without-odbc --without-debugger --without-et --enable-builtin-zlib --without-javac CFLAGS="-g -O2 -march=native"
+    //  to:
+    //      user_build_options: ["--without-wx", "--without-observer", "--without-odbc", "--without-debugger", "--without-et", "--enable-builtin-zlib", "--without-javac", "CFLAGS=-g -O2 -march=native"]
+    let mut user_build_options: Vec<String> = shell_words::split(&settings.options)?;
+    // basic configure options must always include a prefix
+    let mut build_options = vec![
+        "--prefix".to_string(),
+        install_dir.to_str().unwrap().to_string(),
+    ];
+    // append the user defined options
+    build_options.append(&mut user_build_options);
+
+    // a build profile can replace the default target
+    let make_steps: Vec<BuildStep<'static>> = if settings.make_targets.is_empty() {
+        vec![BuildStep::Exec(
+            "make",
+            vec!["-j".to_string(), num_cpus.to_string()],
+        )]
+    } else {
+        settings
+            .make_targets
+            .iter()
+            .map(|target| {
+                BuildStep::Exec(
+                    "make",
+                    vec!["-j".to_string(), num_cpus.to_string(), target.clone()],
+                )
+            })
+            .collect()
+    };
+
+    // declare the build pipeline steps
+    let mut steps = vec![
+        BuildStep::Exec("./configure", build_options),
+        BuildStep::Check(Box::new(|context| {
+            if has_openssl(context.src_dir) {
+                CheckResult::Success
+            } else {
+                CheckResult::Warning("No usable OpenSSL found, please specify one with --with-ssl configure option, `crypto` application will not work in current build")
+            }
+        })),
+    ];
+    steps.extend(make_steps);
+    // extra emulator types are built after the default one since they reuse
+    // what it built for everything but the emulator itself
+    for emu_type in settings.emu_types.iter() {
+        steps.push(BuildStep::Exec(
+            "make",
+            vec![
+                "-j".to_string(),
+                num_cpus.to_string(),
+                format!("TYPE={}", emu_type.as_str()),
+            ],
+        ));
+    }
+
+    // the doc chunks used by the shell's `h/1` are built without any extra
+    // tools but HTML docs need ex_doc, without it only the chunks are built
+    let doc_options = match settings.docs {
+        Docs::None => None,
+        Docs::Chunks => Some(vec!["DOC_TARGETS=chunks".to_string()]),
+        Docs::Html => match find_ex_doc() {
+            Some(ex_doc) => {
+                debug!("found ex_doc at {ex_doc:?}");
+                Some(vec![
+                    "DOC_TARGETS=chunks html".to_string(),
+                    format!("EX_DOC={}", ex_doc.display()),
+                ])
+            }
+            None => {
+                steps.push(BuildStep::Check(Box::new(|_| {
+                    CheckResult::Warning(
+                        "No ex_doc found on the PATH, in ~/.mix/escripts or in the bin dir of the Elixir install in use, skipping HTML docs",
+                    )
+                })));
+                Some(vec!["DOC_TARGETS=chunks".to_string()])
+            }
+        },
+    };
+    if let Some(doc_options) = &doc_options {
+        let mut docs = vec!["-j".to_string(), num_cpus.to_string(), "docs".to_string()];
+        docs.extend(doc_options.iter().cloned());
+        steps.push(BuildStep::Exec("make", docs));
+    }
+
+    steps.extend(vec![
+        build::check_build_status(),
+        BuildStep::Exec(
+            "make",
+            vec![
+                "-j".to_string(),
+                num_cpus.to_string(),
+                "install".to_string(),
+            ],
+        ),
+    ]);
+    if let Some(doc_options) = doc_options {
+        let mut install_docs = vec!["install-docs".to_string()];
+        install_docs.extend(doc_options);
+        steps.push(BuildStep::Exec("make", install_docs));
+    }
+    // installing the emulator of a type puts its beam next to the default one
+    for emu_type in settings.emu_types.iter() {
+        steps.push(BuildStep::Exec(
+            "make",
+            vec![
+                "install.emulator".to_string(),
+                format!("TYPE={}", emu_type.as_str()),
+            ],
+        ));
+    }
+
+    Ok(steps)
+}
+
+// ex_doc is looked for on the PATH and where `mix escript.install hex ex_doc`
+// of an installed Elixir puts it
+fn find_ex_doc() -> Option<PathBuf> {
+    let mut dirs = env::var_os("PATH")
+        .map(|path| split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if let Some(home_dir) = dirs::home_dir() {
+        dirs.push(home_dir.join(".mix").join("escripts"));
+    }
+    if let Ok(elixir_dir) = config::install_to_use_by_language(Language::Elixir) {
+        dirs.push(Path::new(&elixir_dir).join("bin"));
+    }
+
+    dirs.into_iter()
+        .map(|dir| dir.join("ex_doc"))
+        .find(|ex_doc| ex_doc.is_file())
+}
+
+// OTP can be configured to build without the terminal and crypto support that
+// need ncurses and OpenSSL, or with OpenSSL from a given dir
+pub fn build_dependencies(user_build_options0: &str) -> Result<Vec<Dependency>> {
+    let user_build_options: Vec<String> = shell_words::split(user_build_options0)?;
+    let mut dependencies = vec![Dependency::CCompiler, Dependency::Make, Dependency::Perl];
+
+    if !user_build_options.iter().any(|o| o == "--without-termcap") {
+        dependencies.push(Dependency::Ncurses);
+    }
+    if !user_build_options.iter().any(|o| o == "--without-ssl") {
+        let ssl_dir = user_build_options
+            .iter()
+            .find_map(|o| o.strip_prefix("--with-ssl="))
+            .map(PathBuf::from);
+        dependencies.push(Dependency::OpenSsl(ssl_dir));
+    }
+
+    Ok(dependencies)
+}
+
+fn has_openssl(src_dir: &Path) -> bool {
+    // check that lib/crypto/SKIP doesn't exist,
+    // if it does it means something went wrong with OpenSSL
+    !src_dir.join("./lib/crypto/SKIP").exists()
+}
+
+// for languages whose build runs `erl` and `erlc`, puts the active Erlang
+// install first in the PATH
+pub fn build_env() -> Result<Vec<(String, String)>> {
+    let erlang_dir = config::install_to_use_by_language(Language::Erlang)?;
+
+    let path = env::var("PATH")?;
+    let mut paths = split_paths(&path).collect::<Vec<_>>();
+    paths.insert(0, Path::new(&erlang_dir).join("bin"));
+    let new_path = join_paths(paths)?;
+
+    Ok(vec![(
+        "PATH".to_string(),
+        new_path.into_string().unwrap_or(path),
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_dependencies_from_options() {
+        assert_eq!(
+            build_dependencies("").unwrap(),
+            vec![
+                Dependency::CCompiler,
+                Dependency::Make,
+                Dependency::Perl,
+                Dependency::Ncurses,
+                Dependency::OpenSsl(None),
+            ]
+        );
+
+        let dependencies =
+            build_dependencies("--without-termcap --without-ssl --without-wx").unwrap();
+        assert!(!dependencies.contains(&Dependency::Ncurses));
+        assert!(!dependencies
+            .iter()
+            .any(|d| matches!(d, Dependency::OpenSsl(_))));
+
+        let dependencies = build_dependencies("--with-ssl=/opt/openssl CFLAGS=\"-g -O2\"").unwrap();
+        assert!(dependencies.contains(&Dependency::OpenSsl(Some(PathBuf::from("/opt/openssl")))));
+        assert!(dependencies.contains(&Dependency::Ncurses));
+
+        // only whole options count, not ones quoted inside another
+        let dependencies = build_dependencies("CFLAGS=\"--without-ssl\"").unwrap();
+        assert!(dependencies.contains(&Dependency::OpenSsl(None)));
+
+        assert!(build_dependencies("CFLAGS=\"-g").is_err());
+    }
+}
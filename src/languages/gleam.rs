@@ -1,6 +1,9 @@
+use crate::cmd::build;
+use crate::cmd::build::{BuildSettings, BuildStep};
 use crate::languages::Language;
 use color_eyre::eyre::{eyre, Result, WrapErr};
 use regex::Regex;
+use std::path::Path;
 
 #[cfg(unix)]
 pub fn bins() -> Vec<(String, Language)> {
@@ -17,16 +20,55 @@ pub fn bins() -> Vec<(String, Language)> {
 
 pub fn asset_prefix() -> Result<regex::Regex> {
     match (std::env::consts::ARCH, std::env::consts::OS) {
-        ("x86_64", "linux") => Regex::new("gleam-.*-x86_64-unknown-linux-musl.tar.gz")
+        ("x86_64", "linux") => Regex::new("gleam-.*-x86_64-unknown-linux-musl.tar.(gz|xz|zst)")
             .wrap_err("Unable to create asset regex"),
-        ("aarch64", "linux") => Regex::new("gleam-.*-aarch64-unknown-linux-musl.tar.gz")
+        ("aarch64", "linux") => Regex::new("gleam-.*-aarch64-unknown-linux-musl.tar.(gz|xz|zst)")
             .wrap_err("Unable to create asset regex"),
-        ("x86_64", "macos") => Regex::new("gleam-.*-x86_64-apple-darwin.tar.gz")
+        ("x86_64", "macos") => Regex::new("gleam-.*-x86_64-apple-darwin.tar.(gz|xz|zst)")
             .wrap_err("Unable to create asset regex"),
-        ("aarch64", "macos") => Regex::new("gleam-.*-aarch64-apple-darwin.tar.gz")
+        ("aarch64", "macos") => Regex::new("gleam-.*-aarch64-apple-darwin.tar.(gz|xz|zst)")
             .wrap_err("Unable to create asset regex"),
         ("x86_64", "windows") => Regex::new("gleam-.*-x86_64-pc-windows-msvc.zip")
             .wrap_err("Unable to create asset regex"),
         _ => Err(eyre!("Unknown architecture or OS for installing gleam")),
     }
 }
+
+// the compiler is built with cargo and installed with `cargo install --root` so
+// the `gleam` binary ends up in `<install_dir>/bin` like a binary install. Any
+// user build options, like `--features`, are passed to both cargo commands.
+pub fn build_steps(
+    install_dir: &Path,
+    settings: &BuildSettings,
+) -> Result<Vec<BuildStep<'static>>> {
+    if !settings.make_targets.is_empty() {
+        return Err(eyre!("Gleam is built with cargo and has no make targets"));
+    }
+    let user_build_options: Vec<String> = shell_words::split(&settings.options)?;
+
+    let mut build_options = vec![
+        "build".to_string(),
+        "--release".to_string(),
+        "--locked".to_string(),
+    ];
+    build_options.append(&mut user_build_options.clone());
+
+    // reuse the target dir of the build so `cargo install` doesn't compile again
+    let mut install_options = vec![
+        "install".to_string(),
+        "--path".to_string(),
+        "compiler-cli".to_string(),
+        "--root".to_string(),
+        install_dir.to_str().unwrap().to_string(),
+        "--locked".to_string(),
+        "--target-dir".to_string(),
+        "target".to_string(),
+    ];
+    install_options.append(&mut user_build_options.clone());
+
+    Ok(vec![
+        BuildStep::Exec("cargo", build_options),
+        build::check_build_status(),
+        BuildStep::Exec("cargo", install_options),
+    ])
+}
@@ -0,0 +1,27 @@
+use crate::cmd::build;
+use crate::cmd::build::{BuildSettings, BuildStep};
+use crate::languages::Language;
+use color_eyre::eyre::{eyre, Result};
+use std::path::Path;
+
+pub fn bins() -> Vec<(String, Language)> {
+    vec![
+        ("lfe".to_string(), Language::Lfe),
+        ("lfec".to_string(), Language::Lfe),
+        ("lfescript".to_string(), Language::Lfe),
+    ]
+}
+
+// LFE doesn't publish binary releases, it can only be built
+pub fn asset_prefix() -> Result<regex::Regex> {
+    Err(eyre!("No binary releases available for LFE"))
+}
+
+// like Elixir there is no configure step, any user build options are passed
+// to `make` as variables
+pub fn build_steps(
+    install_dir: &Path,
+    settings: &BuildSettings,
+) -> Result<Vec<BuildStep<'static>>> {
+    build::make_steps(install_dir, settings)
+}
@@ -19,12 +19,17 @@ mod config;
 
 use color_eyre::{config::HookBuilder, eyre::eyre, eyre::Report, eyre::Result};
 
+mod archive;
+mod cache;
 mod cmd;
 mod components;
+mod dependencies;
 mod git;
 mod github;
+mod interrupt;
 mod languages;
 mod links;
+mod mirror;
 mod run;
 mod utils;
 
@@ -74,11 +79,20 @@ enum SubCommands {
     Default(IdArgs),
 
     /// Deletes an install by id
-    Delete(IdArgs),
+    Delete(DeleteArgs),
 
     /// Build and install by branch of tag name
     Build(BuildArgs),
 
+    /// Build an install again the same way it was built
+    Rebuild(IdArgs),
+
+    /// Show the log of a build by id
+    BuildLog(IdArgs),
+
+    /// Show the details of an install by id
+    Info(IdArgs),
+
     /// Install binary release of language
     Install(InstallArgs),
 
@@ -90,6 +104,12 @@ enum SubCommands {
 
     /// Add or remove a link to an existing install
     Link(LinkSubCommands),
+
+    /// Manage the download cache
+    Cache(CacheSubCommands),
+
+    /// Export installs to a bundle or import them from one, for offline machines
+    Bundle(BundleSubCommands),
 }
 
 #[derive(Args, Debug)]
@@ -110,6 +130,9 @@ struct ReleasesArgs {
 
 #[derive(Args, Debug)]
 struct RepoArgs {
+    /// Language of the source repo
+    language: languages::Language,
+
     /// Which repo to use for command
     #[arg(short, long)]
     repo: Option<String>,
@@ -124,6 +147,19 @@ struct IdArgs {
     id: String,
 }
 
+#[derive(Args, Debug)]
+struct DeleteArgs {
+    /// Language to delete an install of
+    language: languages::Language,
+
+    /// Id of the install
+    id: String,
+
+    /// Delete even if the install is used in the current directory's .beamup.toml
+    #[arg(short, long)]
+    force: bool,
+}
+
 #[derive(Args, Debug)]
 struct BuildArgs {
     /// Language to build a release or branch of
@@ -136,6 +172,18 @@ struct BuildArgs {
     #[arg(short, long)]
     branch: Option<String>,
 
+    /// Commit SHA of the repo
+    #[arg(short, long, conflicts_with_all = ["release", "branch"])]
+    sha: Option<String>,
+
+    /// Build a local source checkout instead of downloading one
+    #[arg(short, long, conflicts_with_all = ["release", "branch", "sha"])]
+    path: Option<PathBuf>,
+
+    /// Build a copy of the --path checkout so the checkout stays clean
+    #[arg(long, requires = "path")]
+    out_of_tree: bool,
+
     /// Id to give the build
     #[arg(short, long)]
     id: Option<String>,
@@ -144,6 +192,18 @@ struct BuildArgs {
     #[arg(short, long)]
     repo: Option<String>,
 
+    /// Build profile from the config to build with
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Extra Erlang emulator types to build and install, like debug,asan
+    #[arg(long, value_delimiter = ',')]
+    emu_types: Vec<languages::erlang::EmuType>,
+
+    /// Which Erlang docs to build and install, html needs ex_doc [default: chunks]
+    #[arg(long)]
+    docs: Option<languages::erlang::Docs>,
+
     /// Forces a build disregarding any previously existing ones
     #[arg(short, long)]
     force: bool,
@@ -155,7 +215,8 @@ struct InstallArgs {
     language: languages::Language,
 
     /// Release version to install
-    release: String,
+    #[arg(required_unless_present_any = ["from_file", "from_url"])]
+    release: Option<String>,
 
     /// Id to give the install
     #[arg(short, long)]
@@ -172,6 +233,14 @@ struct InstallArgs {
     /// For Erlang only. Select the libc the install wil be built to dynamically link against.
     #[arg(short, long)]
     libc: Option<languages::Libc>,
+
+    /// Install a local release archive instead of downloading one
+    #[arg(long, requires = "id", conflicts_with_all = ["from_url", "repo", "libc"])]
+    from_file: Option<PathBuf>,
+
+    /// Install a release archive downloaded from the URL
+    #[arg(long, requires = "id", conflicts_with_all = ["repo", "libc"])]
+    from_url: Option<String>,
 }
 
 #[derive(Args, Debug)]
@@ -184,6 +253,40 @@ struct ComponentSubCommands {
 enum ComponentCmds {
     /// Install a component
     Install(ComponentInstallArgs),
+
+    /// List installed components
+    List,
+
+    /// Set default install of a component to use by id
+    Default(ComponentIdArgs),
+
+    /// Switch install of a component to use by id
+    Switch(ComponentIdArgs),
+
+    /// Deletes an install of a component by id
+    Delete(ComponentDeleteArgs),
+}
+
+#[derive(Args, Debug)]
+struct ComponentIdArgs {
+    /// Component to use
+    component: components::Kind,
+
+    /// Id of the install
+    id: String,
+}
+
+#[derive(Args, Debug)]
+struct ComponentDeleteArgs {
+    /// Component to delete an install of
+    component: components::Kind,
+
+    /// Id of the install
+    id: String,
+
+    /// Delete even if the install is used in the current directory's .beamup.toml
+    #[arg(short, long)]
+    force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -207,6 +310,57 @@ struct ComponentInstallArgs {
     force: bool,
 }
 
+#[derive(Args, Debug)]
+struct CacheSubCommands {
+    #[command(subcommand)]
+    cmd: CacheCmds,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCmds {
+    /// List cached downloads
+    Ls,
+
+    /// Remove all cached downloads
+    Clean,
+}
+
+#[derive(Args, Debug)]
+struct BundleSubCommands {
+    #[command(subcommand)]
+    cmd: BundleCmds,
+}
+
+#[derive(Subcommand, Debug)]
+enum BundleCmds {
+    /// Pack installs and their config into a bundle
+    Export(BundleExportArgs),
+
+    /// Unpack the installs of a bundle and add them to the config
+    Import(BundleImportArgs),
+}
+
+#[derive(Args, Debug)]
+struct BundleExportArgs {
+    /// Installs to export, like `erlang:27.1` or `rebar3:3.24.0`
+    #[arg(required = true)]
+    installs: Vec<cmd::bundle::BundleInstall>,
+
+    /// File to write the bundle to, a zstd compressed tarball
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Args, Debug)]
+struct BundleImportArgs {
+    /// Bundle to import
+    file: PathBuf,
+
+    /// Replace any existing installs with the same ids
+    #[arg(short, long)]
+    force: bool,
+}
+
 #[derive(Args, Debug)]
 struct RepoSubCommands {
     #[command(subcommand)]
@@ -227,8 +381,8 @@ enum RepoCmds {
 
 #[derive(Args, Debug)]
 struct RepoAddArgs {
-    /// Language to add repo for
-    language: languages::Language,
+    /// Language or component to add repo for
+    tool: config::Tool,
 
     /// id of the repo to add
     id: String,
@@ -239,8 +393,8 @@ struct RepoAddArgs {
 
 #[derive(Args, Debug)]
 struct RepoRmArgs {
-    /// Language to remove repo for
-    language: languages::Language,
+    /// Language or component to remove repo for
+    tool: config::Tool,
 
     /// id of the repo to remove
     id: String,
@@ -271,6 +425,10 @@ struct LinkAddArgs {
 
     /// Path of the existing installation
     path: String,
+
+    /// Replace an existing install with the same id
+    #[arg(short, long)]
+    force: bool,
 }
 
 #[derive(Args, Debug)]
@@ -317,12 +475,25 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
             cmd::list::run(&config);
             Ok(())
         }
-        SubCommands::Releases(ReleasesArgs { language, .. }) => {
-            debug!("running releases: language={:?}", language);
+        SubCommands::Releases(ReleasesArgs { language, repo }) => {
+            debug!("running releases: language={:?} repo={:?}", language, repo);
 
-            // TODO: should return Result type
-            cmd::releases::run(language);
-            Ok(())
+            cmd::releases::run(language, repo, &config)
+        }
+        SubCommands::Fetch(RepoArgs { language, repo }) => {
+            debug!("running fetch: language={:?} repo={:?}", language, repo);
+
+            cmd::fetch::run(language, repo, &config)
+        }
+        SubCommands::Tags(RepoArgs { language, repo }) => {
+            debug!("running tags: language={:?} repo={:?}", language, repo);
+
+            cmd::tags::run(language, repo, &config)
+        }
+        SubCommands::Branches(RepoArgs { language, repo }) => {
+            debug!("running branches: language={:?} repo={:?}", language, repo);
+
+            cmd::branches::run(language, repo, &config)
         }
         SubCommands::Install(InstallArgs {
             language,
@@ -331,27 +502,46 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
             repo,
             force,
             libc,
+            from_file,
+            from_url,
         }) => {
             debug!(
-                "running install: {:?} {} {:?} {:?} {:?} {:?}",
-                language, release, id, repo, force, libc
+                "running install: {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+                language, release, id, repo, force, libc, from_file, from_url
             );
 
             check_if_install_supported(language)?;
 
-            // if no user supplied id then use the name of
-            // the release to install
-            let id = id.as_ref().unwrap_or(release);
+            // an archive from a file or URL is installed with the given id,
+            // which is also its release unless one is given
+            let (id, source) = match (from_file, from_url, id, release) {
+                (Some(file), _, Some(id), _) => {
+                    (id, cmd::install::InstallSource::File(file.clone()))
+                }
+                (_, Some(url), Some(id), _) => (id, cmd::install::InstallSource::Url(url.clone())),
+                // if no user supplied id then use the name of
+                // the release to install
+                (_, _, id, Some(release)) => (
+                    id.as_ref().unwrap_or(release),
+                    cmd::install::InstallSource::Release {
+                        release,
+                        libc,
+                        repo,
+                    },
+                ),
+                _ => return Err(eyre!("No release, --from-file or --from-url to install")),
+            };
+            let release = release.as_ref().unwrap_or(id);
 
             info!(
                 "Downloading and installing {:?} for release={} id={}",
                 language, release, id
             );
 
-            let dir = cmd::install::run(language, id, release, libc, *force)?;
+            let (dir, metadata) = cmd::install::run(language, id, &source, *force, &config)?;
             cmd::update_links::run(Some(language), &config)?;
 
-            config::add_install(language, id, release, dir, config_file, config)?;
+            config::add_install(language, id, release, dir, metadata, config_file, config)?;
 
             info!(
                 "Completed install of {:?} for release={} id={}",
@@ -386,70 +576,154 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
 
             cmd::switch::run(language, id, config)
         }
+        SubCommands::Delete(DeleteArgs {
+            language,
+            id,
+            force,
+        }) => {
+            debug!("running delete: {:?} {:?} {:?}", language, id, force);
+
+            cmd::delete::run(language, id, *force, config_file, config)?;
+
+            info!("Deleted {:?} install of id={}", language, id);
+
+            Ok(())
+        }
         SubCommands::Build(BuildArgs {
             language,
             release,
             branch,
+            sha,
+            path,
+            out_of_tree,
             id,
             repo,
+            profile,
+            emu_types,
+            docs,
             force,
         }) => {
             debug!(
-                "running build: {:?} {:?} {:?} {:?} {:?} {:?}",
-                language, release, branch, id, repo, force
+                "running build: {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+                language,
+                release,
+                branch,
+                sha,
+                path,
+                out_of_tree,
+                id,
+                repo,
+                profile,
+                emu_types,
+                docs,
+                force
             );
 
-            if *language != languages::Language::Erlang {
-                return Err(eyre!(
-                    "build command not supported yet for language {language:?}"
-                ));
+            check_if_build_supported(language)?;
+            if !emu_types.is_empty() && *language != languages::Language::Erlang {
+                return Err(eyre!("--emu-types is only supported for Erlang builds"));
             }
-
-            if std::env::consts::OS == "windows" {
-                return Err(eyre!("build command not supported yet for Windows"));
+            if docs.is_some() && *language != languages::Language::Erlang {
+                return Err(eyre!("--docs is only supported for Erlang builds"));
             }
 
-            let git_ref = match release {
-                None => match branch {
-                    None => {
-                        return Err(eyre!(
-                            "build command needs a release argument or the -b <branch> option"
-                        ))
-                    }
-                    Some(branch) => git::GitRef::Branch(branch.to_owned()),
+            let source = match (release, branch, sha, path) {
+                (Some(release), _, _, _) => {
+                    cmd::build::BuildSource::Ref(git::GitRef::Release(release.to_owned()))
+                }
+                (None, Some(branch), _, _) => {
+                    cmd::build::BuildSource::Ref(git::GitRef::Branch(branch.to_owned()))
+                }
+                (None, None, Some(sha), _) => {
+                    cmd::build::BuildSource::Ref(git::GitRef::Commit(sha.to_owned()))
+                }
+                (None, None, None, Some(path)) => cmd::build::BuildSource::Path {
+                    dir: path.to_owned(),
+                    out_of_tree: *out_of_tree,
                 },
-                Some(release) => git::GitRef::Release(release.to_owned()),
+                (None, None, None, None) => {
+                    return Err(eyre!(
+                        "build command needs a release argument, the -b <branch>, -s <sha> or -p <path> option"
+                    ))
+                }
             };
-            let id = id.clone().unwrap_or(git_ref.to_string());
-
-            info!("Building {:?} for ref={} id={}", language, git_ref, id);
-            let dir = cmd::build::run(language, &git_ref, &id, repo, *force, &config)?;
 
-            cmd::update_links::run(Some(language), &config)?;
+            // a path makes for a poor id, so one must be given
+            let id = match (id, &source) {
+                (Some(id), _) => id.clone(),
+                (None, cmd::build::BuildSource::Ref(git_ref)) => git_ref.to_string(),
+                (None, cmd::build::BuildSource::Path { .. }) => {
+                    return Err(eyre!(
+                        "build command needs the -i <id> option with -p <path>"
+                    ))
+                }
+            };
 
-            config::add_install(
+            info!("Building {:?} for source={} id={}", language, source, id);
+            let flags = cmd::build::BuildFlags {
+                profile: profile.clone(),
+                emu_types: emu_types.clone(),
+                docs: docs.unwrap_or_default(),
+            };
+            let recipe = cmd::build::recipe(language, &source, repo, &flags, &config)?;
+            build_install(
                 language,
+                &recipe,
                 &id,
-                &git_ref.to_string(),
-                dir,
+                &source.to_string(),
+                *force,
                 config_file,
                 config,
             )?;
 
             info!(
-                "Completed build and install of {:?} for ref={} id={}",
-                language, git_ref, id
+                "Completed build and install of {:?} for source={} id={}",
+                language, source, id
             );
 
             Ok(())
         }
+        SubCommands::Rebuild(IdArgs { language, id }) => {
+            debug!("running rebuild: {:?} {:?}", language, id);
+
+            check_if_build_supported(language)?;
+
+            let metadata = config::lookup_install_metadata(language, id, &config)?;
+            let recipe = cmd::build::BuildRecipe::from_metadata(&metadata)?;
+            let release = match metadata.get("release") {
+                Some(toml::Value::String(release)) => release.to_owned(),
+                _ => recipe.git_ref.clone(),
+            };
+
+            info!(
+                "Rebuilding {:?} id={} from source={}",
+                language,
+                id,
+                recipe.build_source()
+            );
+            build_install(language, &recipe, id, &release, true, config_file, config)?;
+
+            info!("Completed rebuild of {:?} id={}", language, id);
+
+            Ok(())
+        }
+        SubCommands::Info(IdArgs { language, id }) => {
+            debug!("running info: {:?} {:?}", language, id);
+
+            cmd::info::run(language, id, &config)
+        }
+        SubCommands::BuildLog(IdArgs { language, id }) => {
+            debug!("running build-log: {:?} {:?}", language, id);
+
+            cmd::build_log::run(language, id)
+        }
         SubCommands::Component(ComponentSubCommands {
             cmd:
                 ComponentCmds::Install(ComponentInstallArgs {
                     component,
                     release,
                     id,
-                    repo: _repo,
+                    repo,
                     force,
                 }),
         }) => {
@@ -463,7 +737,8 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
 
             let c = components::Component::new(component.clone(), release)?;
 
-            let release_dir = cmd::component_install::run(&c, release, *force)?;
+            let (release_dir, metadata) =
+                cmd::component_install::run(&c, release, repo, *force, &config)?;
 
             let bin_dir = config::bin_dir();
             let _ = std::fs::create_dir_all(&bin_dir);
@@ -477,6 +752,7 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
                 id,
                 &release.to_string(),
                 release_dir.to_string(),
+                metadata,
                 config_file,
                 config,
             )?;
@@ -485,8 +761,157 @@ fn handle_command(_bin_path: PathBuf) -> Result<(), Report> {
 
             Ok(())
         }
+        SubCommands::Component(ComponentSubCommands {
+            cmd: ComponentCmds::List,
+        }) => {
+            debug!("running component list");
+            cmd::component_list::run(&config);
+            Ok(())
+        }
+        SubCommands::Component(ComponentSubCommands {
+            cmd: ComponentCmds::Default(ComponentIdArgs { component, id }),
+        }) => {
+            debug!("running component default: {:?} {:?}", component, id);
+
+            info!(
+                "Setting default {:?} to use to install of id={}",
+                component, id
+            );
+
+            cmd::component_default::run(component, id, config_file, config)
+        }
+        SubCommands::Component(ComponentSubCommands {
+            cmd: ComponentCmds::Switch(ComponentIdArgs { component, id }),
+        }) => {
+            debug!("running component switch: {:?} {:?}", component, id);
+
+            info!(
+                "Switching local {:?} to use install of id={}",
+                component, id
+            );
+
+            cmd::component_switch::run(component, id, config)
+        }
+        SubCommands::Component(ComponentSubCommands {
+            cmd:
+                ComponentCmds::Delete(ComponentDeleteArgs {
+                    component,
+                    id,
+                    force,
+                }),
+        }) => {
+            debug!(
+                "running component delete: {:?} {:?} {:?}",
+                component, id, force
+            );
+
+            cmd::component_delete::run(component, id, *force, config_file, config)?;
+
+            info!("Deleted {:?} install of id={}", component, id);
+
+            Ok(())
+        }
+        SubCommands::Repo(RepoSubCommands {
+            cmd: RepoCmds::Add(RepoAddArgs { tool, id, repo }),
+        }) => {
+            debug!("running repo add: {:?} {:?} {:?}", tool, id, repo);
+
+            cmd::repo::add(tool, id, repo, config_file, config)?;
+
+            info!("Added repo {} for {} with id={}", repo, tool, id);
+
+            Ok(())
+        }
+        SubCommands::Repo(RepoSubCommands {
+            cmd: RepoCmds::Rm(RepoRmArgs { tool, id }),
+        }) => {
+            debug!("running repo rm: {:?} {:?}", tool, id);
+
+            cmd::repo::rm(tool, id, config_file, config)?;
+
+            info!("Removed repo with id={} for {}", id, tool);
+
+            Ok(())
+        }
+        SubCommands::Cache(CacheSubCommands { cmd: CacheCmds::Ls }) => {
+            debug!("running cache ls");
+            cmd::cache::ls(&config)
+        }
+        SubCommands::Cache(CacheSubCommands {
+            cmd: CacheCmds::Clean,
+        }) => {
+            debug!("running cache clean");
+            cmd::cache::clean()?;
+
+            info!("Removed all cached downloads");
+
+            Ok(())
+        }
+        SubCommands::Bundle(BundleSubCommands {
+            cmd: BundleCmds::Export(BundleExportArgs { installs, output }),
+        }) => {
+            debug!("running bundle export: {:?} {:?}", installs, output);
+
+            cmd::bundle::export(installs, output, &config)?;
 
-        _ => Err(eyre!("subcommand not implemented yet")),
+            info!(
+                "Exported {} installs to {}",
+                installs.len(),
+                output.display()
+            );
+
+            Ok(())
+        }
+        SubCommands::Bundle(BundleSubCommands {
+            cmd: BundleCmds::Import(BundleImportArgs { file, force }),
+        }) => {
+            debug!("running bundle import: {:?} {:?}", file, force);
+
+            let imported = cmd::bundle::import(file, *force, config_file, config)?;
+
+            for install in imported {
+                info!("Imported {install}");
+            }
+
+            Ok(())
+        }
+        SubCommands::Repo(RepoSubCommands { cmd: RepoCmds::Ls }) => {
+            debug!("running repo ls");
+            cmd::repo::ls(&config);
+            Ok(())
+        }
+        SubCommands::Link(LinkSubCommands {
+            cmd:
+                LinkCmds::Add(LinkAddArgs {
+                    language,
+                    id,
+                    path,
+                    force,
+                }),
+        }) => {
+            debug!(
+                "running link add: {:?} {:?} {:?} {:?}",
+                language, id, path, force
+            );
+
+            let dir = cmd::link::add(language, id, path, *force, config_file, config.clone())?;
+            cmd::update_links::run(Some(language), &config)?;
+
+            info!("Linked {:?} install of id={} to {}", language, id, dir);
+
+            Ok(())
+        }
+        SubCommands::Link(LinkSubCommands {
+            cmd: LinkCmds::Rm(LinkRmArgs { language, id }),
+        }) => {
+            debug!("running link rm: {:?} {:?}", language, id);
+
+            cmd::link::rm(language, id, config_file, config)?;
+
+            info!("Removed link to {:?} install of id={}", language, id);
+
+            Ok(())
+        }
     }
 }
 
@@ -510,17 +935,62 @@ fn check_if_install_supported(language: &languages::Language) -> Result<()> {
         }
     }
 
+    if *language == languages::Language::Lfe {
+        return Err(eyre!(
+            "install command not supported for language {language:?}, there are no binary releases. Use `beamup build lfe <release>` instead."
+        ));
+    }
+
     if *language == languages::Language::Elixir {
-        // catch when no Erlang is installed and made the default
-        match config::get_otp_major_vsn() {
-            Err(_) => return Err(eyre!("No default Erlang installation found. Install an Erlang version, like `beamup install erlang latest` or set a default with `beamup default erlang <ID>` first.")),
-            _ => ()
-        }
+        check_erlang_installed()?;
+    }
+
+    Ok(())
+}
+
+// catch when no Erlang is installed and made the default
+fn check_erlang_installed() -> Result<()> {
+    if config::get_otp_major_vsn().is_err() {
+        return Err(eyre!("No default Erlang installation found. Install an Erlang version, like `beamup install erlang latest` or set a default with `beamup default erlang <ID>` first."));
     }
 
     Ok(())
 }
 
+// builds and adds the install to the config, a failed build leaves the install
+// it was replacing and its config entry, with the recipe to rebuild it, as they
+// were
+fn build_install(
+    language: &languages::Language,
+    recipe: &cmd::build::BuildRecipe,
+    id: &String,
+    release: &String,
+    force: bool,
+    config_file: String,
+    config: config::Config,
+) -> Result<()> {
+    let (dir, metadata) = cmd::build::run(language, recipe, id, force, &config)?;
+
+    cmd::update_links::run(Some(language), &config)?;
+
+    config::add_install(language, id, release, dir, metadata, config_file, config)
+}
+
+// only Erlang, Elixir, Gleam and LFE can be built and not on Windows
+fn check_if_build_supported(language: &languages::Language) -> Result<()> {
+    if std::env::consts::OS == "windows" {
+        return Err(eyre!("build command not supported yet for Windows"));
+    }
+
+    match language {
+        languages::Language::Erlang => Ok(()),
+        // Elixir and LFE are built with the default Erlang install
+        languages::Language::Elixir | languages::Language::Lfe => check_erlang_installed(),
+        // Gleam only needs cargo
+        languages::Language::Gleam => Ok(()),
+    }
+}
+
 // ELP and rebar3 don't (yet) provide Windows binaries
 fn check_if_component_install_supported() -> Result<()> {
     match (std::env::consts::ARCH, std::env::consts::OS) {
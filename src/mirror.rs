@@ -0,0 +1,415 @@
+use crate::config;
+use crate::github;
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+static INDEX_FILES: [&str; 2] = ["index.json", "index.toml"];
+
+/// A server or directory with the releases of a language or component, used
+/// instead of Github, like an internal artifact server or a `file://` dir. Its
+/// `index.json` or `index.toml` lists the releases, newest first.
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Index {
+    #[serde(default)]
+    releases: Vec<Release>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag: String,
+    // the source tarball, a URL or a path relative to the mirror
+    source: Option<String>,
+    source_sha256: Option<String>,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    // a URL or a path relative to the mirror, `<tag>/<name>` by default
+    url: Option<String>,
+    sha256: Option<String>,
+}
+
+impl Mirror {
+    pub fn new(url: &str) -> Mirror {
+        Mirror {
+            url: url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn print_releases(&self, config: &config::Config) -> Result<()> {
+        for release in self.index(config)?.releases {
+            println!("{}", release.tag);
+        }
+
+        Ok(())
+    }
+
+    pub fn download_asset(
+        &self,
+        asset_prefix: &regex::Regex,
+        out_dir: &Path,
+        tag: &str,
+        config: &config::Config,
+    ) -> Result<(PathBuf, String)> {
+        let release = self.release(tag, config)?;
+
+        debug!("looking for asset {asset_prefix} in mirror {}", self.url);
+        let asset = release
+            .assets
+            .iter()
+            .filter(|&asset| !github::is_checksum_file(&asset.name))
+            .find(|&asset| asset_prefix.is_match(&asset.name))
+            .ok_or_else(|| {
+                eyre!(
+                    "Release {} found in mirror {} but no asset matching prefix {asset_prefix}",
+                    release.tag,
+                    self.url
+                )
+            })?;
+
+        let url = match &asset.url {
+            Some(url) => self.resolve(url),
+            None => format!("{}/{}/{}", self.url, release.tag, asset.name),
+        };
+        let file = out_dir.join(&asset.name);
+        // like the checksums published on Github, the hex may be in either case
+        let expected_sha256 = asset.sha256.as_ref().map(|sha256| sha256.to_lowercase());
+        let sha256 = self.download(
+            &file,
+            &url,
+            expected_sha256.as_deref(),
+            format!("Downloading release asset from {}", self.url),
+            config,
+        )?;
+
+        Ok((file, sha256))
+    }
+
+    pub fn download_release_tarball(
+        &self,
+        out_dir: &Path,
+        tag: &str,
+        config: &config::Config,
+    ) -> Result<(PathBuf, String)> {
+        let release = self.release(tag, config)?;
+        let url = match &release.source {
+            Some(source) => self.resolve(source),
+            None => {
+                return Err(eyre!(
+                    "No source tarball found for release {} in mirror {}",
+                    release.tag,
+                    self.url
+                ))
+            }
+        };
+
+        let name = url.rsplit('/').next().unwrap_or("source.tar.gz");
+        let file = out_dir.join(name);
+        let expected_sha256 = release
+            .source_sha256
+            .as_ref()
+            .map(|sha256| sha256.to_lowercase());
+        let sha256 = self.download(
+            &file,
+            &url,
+            expected_sha256.as_deref(),
+            format!("Downloading release source tarball from {}", self.url),
+            config,
+        )?;
+
+        Ok((file, sha256))
+    }
+
+    // only a missing `index.json` falls back to `index.toml`, any other error
+    // reading or parsing it is returned
+    fn index(&self, config: &config::Config) -> Result<Index> {
+        for index_file in INDEX_FILES {
+            let url = format!("{}/{index_file}", self.url);
+            debug!("reading release index {url}");
+            match self.read(&url, config)? {
+                Some(index) if index_file.ends_with(".json") => {
+                    return serde_json::from_str(&index)
+                        .wrap_err_with(|| format!("Failed to parse release index {url}"))
+                }
+                Some(index) => {
+                    return toml::from_str(&index)
+                        .wrap_err_with(|| format!("Failed to parse release index {url}"))
+                }
+                None => debug!("no release index at {url}"),
+            }
+        }
+
+        Err(eyre!(
+            "No index.json or index.toml found in mirror {}",
+            self.url
+        ))
+    }
+
+    // `latest` is the first, newest, release of the index
+    fn release(&self, tag: &str, config: &config::Config) -> Result<Release> {
+        let mut releases = self.index(config)?.releases.into_iter();
+        let release = if tag == "latest" {
+            releases.next()
+        } else {
+            releases.find(|release| release.tag == tag)
+        };
+
+        release.ok_or_else(|| eyre!("No release {tag} found in mirror {}", self.url))
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", self.url, url.trim_start_matches('/'))
+        }
+    }
+
+    // `None` if there is no such file or the server responds with a 404
+    fn read(&self, url: &str, config: &config::Config) -> Result<Option<String>> {
+        match url.strip_prefix("file://") {
+            Some(path) => match fs::read_to_string(path) {
+                Ok(contents) => Ok(Some(contents)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e).wrap_err_with(|| format!("Failed to read {path}")),
+            },
+            None => match github::http_get(url, config) {
+                Ok(response) => response
+                    .into_string()
+                    .map(Some)
+                    .wrap_err_with(|| format!("Failed to read {url}")),
+                Err(e) if matches!(e.downcast_ref(), Some(ureq::Error::Status(404, _))) => Ok(None),
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    // files of a `file://` mirror are already local so only downloads from a
+    // server go through the cache
+    fn download(
+        &self,
+        file: &Path,
+        url: &str,
+        expected_sha256: Option<&str>,
+        progress_msg: String,
+        config: &config::Config,
+    ) -> Result<String> {
+        debug!("Downloading {url} to {file:?}");
+        match url.strip_prefix("file://") {
+            Some(path) => {
+                fs::copy(path, file)
+                    .wrap_err_with(|| format!("Failed to copy {path} to {file:?}"))?;
+                github::verify_sha256(file, url, expected_sha256)
+            }
+            None => github::cached_download(file, url, expected_sha256, progress_msg, config),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use tempdir::TempDir;
+
+    // sha256 of "erlang"
+    static ERLANG_SHA256: &str = "cdb8cde8ab8d9e03a81b38304d8f83ce31ce834fc5eee9c695ebbd3384f59b17";
+
+    fn config() -> config::Config {
+        toml::from_str("install_dir = \"\"").unwrap()
+    }
+
+    fn file_mirror(index_file: &str, index: &str) -> (TempDir, Mirror) {
+        let dir = TempDir::new("beamup-test").unwrap();
+        fs::write(dir.path().join(index_file), index).unwrap();
+        let mirror = Mirror::new(&format!("file://{}/", dir.path().display()));
+        (dir, mirror)
+    }
+
+    // serves each path with the status and body given for it, or a 404, to
+    // every request until the test is done
+    fn http_mirror(responses: Vec<(&'static str, u16, &'static str)>) -> Mirror {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+                let (status, body) = responses
+                    .iter()
+                    .find(|(p, _, _)| *p == path)
+                    .map_or((404, ""), |(_, status, body)| (*status, *body));
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        Mirror::new(&url)
+    }
+
+    #[test]
+    fn reads_json_and_toml_indexes() {
+        let (_dir, mirror) = file_mirror(
+            "index.json",
+            r#"{"releases": [{"tag": "OTP-27.1", "assets": [{"name": "otp.tar.gz"}]}, {"tag": "OTP-26.2"}]}"#,
+        );
+        let tags: Vec<String> = mirror
+            .index(&config())
+            .unwrap()
+            .releases
+            .into_iter()
+            .map(|release| release.tag)
+            .collect();
+        assert_eq!(tags, ["OTP-27.1", "OTP-26.2"]);
+
+        let (_dir, mirror) = file_mirror(
+            "index.toml",
+            r#"
+[[releases]]
+tag = "v1.5.0"
+source = "v1.5.0/gleam-v1.5.0.tar.gz"
+
+[[releases.assets]]
+name = "gleam-v1.5.0-x86_64-unknown-linux-musl.tar.gz"
+sha256 = "abc"
+"#,
+        );
+        let release = mirror.release("v1.5.0", &config()).unwrap();
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(release.assets[0].sha256.as_deref(), Some("abc"));
+        assert!(mirror.release("v1.4.0", &config()).is_err());
+    }
+
+    #[test]
+    fn latest_is_the_first_release() {
+        let (_dir, mirror) = file_mirror(
+            "index.json",
+            r#"{"releases": [{"tag": "OTP-27.1"}, {"tag": "OTP-26.2"}]}"#,
+        );
+        assert_eq!(mirror.release("latest", &config()).unwrap().tag, "OTP-27.1");
+        assert_eq!(
+            mirror.release("OTP-26.2", &config()).unwrap().tag,
+            "OTP-26.2"
+        );
+
+        let (_dir, mirror) = file_mirror("index.json", r#"{"releases": []}"#);
+        assert!(mirror.release("latest", &config()).is_err());
+    }
+
+    #[test]
+    fn resolves_asset_urls_and_checksums() {
+        let index = format!(
+            r#"{{"releases": [{{"tag": "OTP-27.1", "assets": [
+                {{"name": "erlang-27.1-x64.tar.gz.sha256"}},
+                {{"name": "erlang-27.1-x64.tar.gz", "sha256": "{}"}},
+                {{"name": "erlang-27.1-arm64.tar.gz", "url": "builds/arm64.tar.gz", "sha256": "{}"}}
+            ]}}]}}"#,
+            ERLANG_SHA256.to_uppercase(),
+            ERLANG_SHA256.replace('d', "0"),
+        );
+        let (dir, mirror) = file_mirror("index.json", &index);
+        fs::create_dir_all(dir.path().join("OTP-27.1")).unwrap();
+        fs::write(dir.path().join("OTP-27.1/erlang-27.1-x64.tar.gz"), "erlang").unwrap();
+        fs::create_dir_all(dir.path().join("builds")).unwrap();
+        fs::write(dir.path().join("builds/arm64.tar.gz"), "erlang").unwrap();
+        let out_dir = TempDir::new("beamup-test").unwrap();
+
+        // assets are at `<tag>/<name>` by default, and checksum files are
+        // never mistaken for the asset
+        let regex = regex::Regex::new("erlang-.*-x64.tar.gz").unwrap();
+        let (file, sha256) = mirror
+            .download_asset(&regex, out_dir.path(), "latest", &config())
+            .unwrap();
+        assert_eq!(file, out_dir.path().join("erlang-27.1-x64.tar.gz"));
+        assert_eq!(sha256, ERLANG_SHA256);
+
+        let regex = regex::Regex::new("erlang-.*-arm64.tar.gz").unwrap();
+        let err = mirror
+            .download_asset(&regex, out_dir.path(), "OTP-27.1", &config())
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"), "{err}");
+        assert!(!out_dir.path().join("erlang-27.1-arm64.tar.gz").exists());
+
+        assert_eq!(
+            mirror.resolve("https://example.com/a.tar.gz"),
+            "https://example.com/a.tar.gz"
+        );
+        assert_eq!(
+            mirror.resolve("/a.tar.gz"),
+            format!("{}/a.tar.gz", mirror.url)
+        );
+    }
+
+    #[test]
+    fn verifies_source_tarballs() {
+        let index = format!(
+            r#"{{"releases": [
+                {{"tag": "OTP-27.1", "source": "src/otp_src_27.1.tar.gz", "source_sha256": "{}"}},
+                {{"tag": "OTP-27.0", "source": "src/otp_src_27.0.tar.gz", "source_sha256": "{}"}},
+                {{"tag": "OTP-26.2"}}
+            ]}}"#,
+            ERLANG_SHA256.to_uppercase(),
+            ERLANG_SHA256.replace('d', "0"),
+        );
+        let (dir, mirror) = file_mirror("index.json", &index);
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/otp_src_27.1.tar.gz"), "erlang").unwrap();
+        fs::write(dir.path().join("src/otp_src_27.0.tar.gz"), "erlang").unwrap();
+        let out_dir = TempDir::new("beamup-test").unwrap();
+
+        let (file, sha256) = mirror
+            .download_release_tarball(out_dir.path(), "OTP-27.1", &config())
+            .unwrap();
+        assert_eq!(file, out_dir.path().join("otp_src_27.1.tar.gz"));
+        assert_eq!(sha256, ERLANG_SHA256);
+
+        let err = mirror
+            .download_release_tarball(out_dir.path(), "OTP-27.0", &config())
+            .unwrap_err();
+        assert!(err.to_string().contains("Checksum mismatch"), "{err}");
+        assert!(!out_dir.path().join("otp_src_27.0.tar.gz").exists());
+
+        let err = mirror
+            .download_release_tarball(out_dir.path(), "OTP-26.2", &config())
+            .unwrap_err();
+        assert!(err.to_string().contains("No source tarball"), "{err}");
+    }
+
+    #[test]
+    fn falls_back_to_toml_index_only_when_json_is_missing() {
+        let mirror = http_mirror(vec![("/index.toml", 200, "[[releases]]\ntag = \"v1\"\n")]);
+        assert_eq!(mirror.release("latest", &config()).unwrap().tag, "v1");
+
+        let mirror = http_mirror(vec![
+            ("/index.json", 500, "oops"),
+            ("/index.toml", 200, "[[releases]]\ntag = \"v1\"\n"),
+        ]);
+        let err = format!("{:?}", mirror.index(&config()).unwrap_err());
+        assert!(err.contains("500"), "{err}");
+
+        let (_dir, mirror) = file_mirror("index.json", "{not json");
+        let err = mirror.index(&config()).unwrap_err().to_string();
+        assert!(err.contains("Failed to parse"), "{err}");
+
+        let (_dir, mirror) = file_mirror("releases.json", "{}");
+        assert!(mirror.index(&config()).is_err());
+    }
+}
@@ -29,10 +29,10 @@ pub fn run_component(bin: &str, kind: &components::Kind, args: Args) -> Result<(
 
         exec(cmd)
     } else {
-        return Err(eyre!(
+        Err(eyre!(
             "Directory of component expected install does not exist: {:?} ",
             install_bin_dir
-        ));
+        ))
     }
 }
 
@@ -60,10 +60,10 @@ pub fn run(bin: &str, args: Args) -> Result<()> {
         debug!("running language cmd {:?}", cmd);
         exec(cmd)
     } else {
-        return Err(eyre!(
+        Err(eyre!(
             "Directory of expected install does not exist: {:?} ",
             install_bin_dir
-        ));
+        ))
     }
 }
 
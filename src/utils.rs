@@ -1,7 +1,8 @@
 use crate::eyre;
+use crate::interrupt;
 use color_eyre::eyre::Result;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn check_release_dir(release_dir: &PathBuf, force: bool) -> Result<()> {
     match release_dir.try_exists() {
@@ -39,3 +40,41 @@ pub fn maybe_create_release_dir(release_dir: &PathBuf, force: bool) -> Result<()
 
     Ok(())
 }
+
+// recursively copies the contents of `from` into `to`, skipping any entries
+// named in `skip` and recreating symlinks instead of following them
+pub fn copy_dir(from: &Path, to: &Path, skip: &[&str]) -> Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in fs::read_dir(from)? {
+        interrupt::check()?;
+        let entry = entry?;
+        if skip.iter().any(|s| entry.file_name() == *s) {
+            continue;
+        }
+
+        let dest = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &dest)?;
+        } else if file_type.is_dir() {
+            copy_dir(&entry.path(), &dest, skip)?;
+        } else {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn copy_symlink(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)?;
+    Ok(())
+}
@@ -0,0 +1,119 @@
+// runs beamup with its own home directory, so the data, config and bin
+// directories a bundle is imported into are the test's and the environment
+// of the test process is left alone
+#![cfg(unix)]
+
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use tempdir::TempDir;
+
+fn beamup(home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_beamup"))
+        .args(args)
+        .env("HOME", home)
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("XDG_BIN_HOME")
+        .output()
+        .unwrap()
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn data_dir(home: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        home.join("Library/Application Support/beamup")
+    } else {
+        home.join(".local/share/beamup")
+    }
+}
+
+fn config_file(home: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        home.join("Library/Application Support/beamup/config.toml")
+    } else {
+        home.join(".config/beamup/config.toml")
+    }
+}
+
+#[test]
+fn exports_and_imports_installs() {
+    let home = TempDir::new("beamup-test").unwrap();
+    let home = home.path();
+
+    let linked_dir = home.join("gleam");
+    fs::create_dir_all(linked_dir.join("bin")).unwrap();
+    fs::write(linked_dir.join("bin/gleam"), "gleam").unwrap();
+    std::os::unix::fs::symlink("gleam", linked_dir.join("bin/gleam2")).unwrap();
+    let linked = linked_dir.to_str().unwrap();
+    assert_success(&beamup(home, &["link", "add", "gleam", "1.0.0", linked]));
+
+    let bundle = home.join("bundle.tar.zst");
+    let bundle = bundle.to_str().unwrap();
+    assert_success(&beamup(
+        home,
+        &["bundle", "export", "gleam:1.0.0", "-o", bundle],
+    ));
+    assert_success(&beamup(home, &["link", "rm", "gleam", "1.0.0"]));
+
+    assert_success(&beamup(home, &["bundle", "import", bundle]));
+    let release_dir = data_dir(home).join("gleam/1.0.0");
+    assert_eq!(
+        fs::read_to_string(release_dir.join("bin/gleam")).unwrap(),
+        "gleam"
+    );
+    assert_eq!(
+        fs::read_link(release_dir.join("bin/gleam2")).unwrap(),
+        PathBuf::from("gleam")
+    );
+    let config: toml::Table = fs::read_to_string(config_file(home))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let install = &config["gleam"]["installs"]["1.0.0"];
+    assert_eq!(install["dir"].as_str(), release_dir.to_str());
+    assert_eq!(install.get("kind"), None);
+
+    // an existing install is only replaced with `-f`
+    assert!(!beamup(home, &["bundle", "import", bundle]).status.success());
+    assert_success(&beamup(home, &["bundle", "import", "-f", bundle]));
+
+    // an id escaping the install directories is refused before anything
+    // is moved or deleted
+    let victim = home.join("victim");
+    fs::create_dir_all(&victim).unwrap();
+    let hostile = home.join("hostile.tar.zst");
+    let manifest = r#"
+[[installs]]
+tool = "gleam"
+id = "../../../../victim"
+
+[installs.metadata]
+release = "1.0.0"
+"#;
+    let mut builder = tar::Builder::new(
+        zstd::Encoder::new(File::create(&hostile).unwrap(), 0)
+            .unwrap()
+            .auto_finish(),
+    );
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    builder
+        .append_data(&mut header, "bundle.toml", manifest.as_bytes())
+        .unwrap();
+    builder.into_inner().unwrap();
+
+    let output = beamup(home, &["bundle", "import", "-f", hostile.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid install"));
+    assert!(victim.exists());
+}